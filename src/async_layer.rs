@@ -0,0 +1,219 @@
+//! Asynchronous layer and job handles.
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, mpsc};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, JoinHandle, Thread};
+
+use crate::{Error, Layer, Measured};
+
+/// Handle of a sent job.
+///
+/// The job is a future of its result, so it can be polled or awaited.
+pub trait Job : Future {
+    /// Cancels the job. Cancelled job results `Error::Cancelled`.
+    fn cancel(&self);
+
+    /// Returns true if the result is available.
+    fn is_finished(&self) -> bool;
+
+    /// Blocks current thread until the job is finished.
+    fn wait(self) -> Self::Output where Self: Sized {
+        struct ThreadWaker(Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut job = Box::pin(self);
+        loop {
+            match job.as_mut().poll(&mut cx) {
+                Poll::Ready(result) => return result,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+}
+
+/// Sends operations without blocking, and receives result via `Job`.
+pub trait AsyncLayer {
+    type Operation;
+    type Qubit;
+    type Slot;
+    type Buffer: Measured<Slot=Self::Slot>;
+    type Response;
+    type Job: Job<Output=Result<(Self::Buffer, Self::Response), Error>>;
+
+    /// Sends operations and returns the job handle.
+    fn send(&mut self, ops: Vec<Self::Operation>) -> Self::Job;
+}
+
+#[derive(Debug)]
+struct JobState<T> {
+    result: Option<Result<T, Error>>,
+    finished: bool,
+    cancelled: bool,
+    waker: Option<Waker>,
+}
+
+impl<T> JobState<T> {
+    fn finish(&mut self, result: Result<T, Error>) {
+        if !self.finished {
+            self.result = Some(result);
+            self.finished = true;
+            if let Some(w) = self.waker.take() {
+                w.wake();
+            }
+        }
+    }
+}
+
+/// Job handle of `ThreadLayer`.
+#[derive(Debug)]
+pub struct ThreadJob<T> {
+    state: Arc<Mutex<JobState<T>>>,
+}
+
+impl<T> Future for ThreadJob<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Job for ThreadJob<T> {
+    /// Cancels the job. A job which is already running is not interrupted,
+    /// but its result is discarded.
+    fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        state.finish(Err(Error::Cancelled));
+    }
+
+    fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished
+    }
+}
+
+type Request<L> = (Vec<<L as Layer>::Operation>,
+                   Arc<Mutex<JobState<(<L as Layer>::Buffer, <L as Layer>::Response)>>>);
+
+/// Lifts a synchronous `Layer` into `AsyncLayer`.
+///
+/// The layer is moved to a worker thread and jobs are executed in sent order.
+/// A panic in the layer is reported as `Error::Backend`.
+#[derive(Debug)]
+pub struct ThreadLayer<L: Layer> {
+    sender: Option<mpsc::Sender<Request<L>>>,
+    worker: Option<JoinHandle<L>>,
+}
+
+impl<L> ThreadLayer<L>
+    where L: Layer + Send + 'static,
+          L::Operation: Send,
+          L::Buffer: Send,
+          L::Response: Send,
+{
+    pub fn new(layer: L) -> Self {
+        let (sender, receiver) = mpsc::channel::<Request<L>>();
+        let worker = thread::spawn(move || {
+            let mut layer = layer;
+            for (ops, state) in receiver {
+                if state.lock().unwrap().cancelled {
+                    continue;
+                }
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    let mut buf = layer.make_buffer();
+                    let response = layer.send_receive(&ops, &mut buf);
+                    (buf, response)
+                })).map_err(|e| {
+                    let msg = e.downcast_ref::<&str>().map(|s| s.to_string())
+                               .or_else(|| e.downcast_ref::<String>().cloned())
+                               .unwrap_or_else(|| "layer panicked".to_string());
+                    Error::Backend(msg.into())
+                });
+                state.lock().unwrap().finish(result);
+            }
+            layer
+        });
+        ThreadLayer { sender: Some(sender), worker: Some(worker) }
+    }
+
+    /// Waits for all sent jobs and takes back the layer.
+    pub fn into_inner(mut self) -> L {
+        drop(self.sender.take());
+        self.worker.take().unwrap().join().unwrap_or_else(|e| panic::resume_unwind(e))
+    }
+}
+
+impl<L> AsyncLayer for ThreadLayer<L>
+    where L: Layer + Send + 'static,
+          L::Operation: Send,
+          L::Buffer: Send,
+          L::Response: Send,
+{
+    type Operation = L::Operation;
+    type Qubit = L::Qubit;
+    type Slot = L::Slot;
+    type Buffer = L::Buffer;
+    type Response = L::Response;
+    type Job = ThreadJob<(L::Buffer, L::Response)>;
+
+    fn send(&mut self, ops: Vec<L::Operation>) -> Self::Job {
+        let state = Arc::new(Mutex::new(JobState { result: None, finished: false, cancelled: false, waker: None }));
+        let job = ThreadJob { state: state.clone() };
+        self.sender.as_ref().unwrap()
+                   .send((ops, state))
+                   .unwrap_or_else(|mpsc::SendError((_, state))| {
+                       state.lock().unwrap().finish(Err(Error::Backend("worker thread is stopped".into())))
+                   });
+        job
+    }
+}
+
+impl<L: Layer> Drop for ThreadLayer<L> {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OpsVec, sim::StateVectorLayer};
+
+    #[test]
+    fn thread_layer() {
+        let mut layer = ThreadLayer::new(StateVectorLayer::new(2));
+        let jobs: Vec<_> = (0..3).map(|_| {
+            let mut ops = OpsVec::<StateVectorLayer>::new();
+            ops.initialize();
+            ops.x(1);
+            ops.measure(1, 0);
+            layer.send(ops.into_vec())
+        }).collect();
+        jobs[1].cancel();
+        let results: Vec<_> = jobs.into_iter().map(|j| j.wait()).collect();
+        assert!(results[0].as_ref().unwrap().0.get(0));
+        assert!(matches!(results[1], Err(Error::Cancelled)));
+        assert!(results[2].as_ref().unwrap().0.get(0));
+
+        let bad = vec![crate::operations::OpArgs::Q(crate::operations::opid::USERDEF, 0)];
+        assert!(matches!(layer.send(bad).wait(), Err(Error::Backend(_))));
+        layer.into_inner();
+    }
+}
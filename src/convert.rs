@@ -1,5 +1,5 @@
 use std::marker::PhantomData;
-use crate::{Layer, Measured, operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation}, gates::{PauliGate, HGate, SGate, TGate, CXGate}};
+use crate::{Error, Layer, TryLayer, Measured, wrapped::{TransparentWrapper, transparent_wrapper}, operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation, RotationOperation, U3Operation, CZOperation, SwapOperation, CCXOperation, CYOperation, ISwapOperation, ConditionalOperation, ResetOperation}, gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate, CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate}};
 
 pub trait Converter<Q1, Q2, S1, S2> {
     fn qconv(q: Q1) -> Q2;
@@ -37,7 +37,7 @@ impl<L: Layer, Q, S, C> Layer for QubitSlotConvertLayer<L, Q, S, C>
 
     fn send(&mut self, ops: &[Self::Operation]) -> Self::Requested {
         self.layer.send(
-            unsafe { std::mem::transmute(ops) }
+            TransparentWrapper::peel_slice(ops)
         )
     }
 
@@ -47,7 +47,28 @@ impl<L: Layer, Q, S, C> Layer for QubitSlotConvertLayer<L, Q, S, C>
 
     fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut Self::Buffer) -> Self::Response {
         self.layer.send_receive(
-            unsafe { std::mem::transmute(ops) },
+            TransparentWrapper::peel_slice(ops),
+            &mut buf.0
+        )
+    }
+}
+
+impl<L: TryLayer, Q, S, C> TryLayer for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot>
+{
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<Self::Requested, Error> {
+        self.layer.try_send(
+            TransparentWrapper::peel_slice(ops)
+        )
+    }
+
+    fn try_receive(&mut self, buf: &mut Self::Buffer) -> Result<Self::Response, Error> {
+        self.layer.try_receive(&mut buf.0)
+    }
+
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut Self::Buffer) -> Result<Self::Response, Error> {
+        self.layer.try_send_receive(
+            TransparentWrapper::peel_slice(ops),
             &mut buf.0
         )
     }
@@ -63,14 +84,33 @@ impl<L: Layer + TGate, Q, S, C> TGate for QubitSlotConvertLayer<L, Q, S, C>
     where C: Converter<Q, L::Qubit, S, L::Slot> {}
 impl<L: Layer + CXGate, Q, S, C> CXGate for QubitSlotConvertLayer<L, Q, S, C>
     where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + RotationGate, Q, S, C> RotationGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + U3Gate, Q, S, C> U3Gate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + CZGate, Q, S, C> CZGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + SwapGate, Q, S, C> SwapGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + CCXGate, Q, S, C> CCXGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + CYGate, Q, S, C> CYGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + ISwapGate, Q, S, C> ISwapGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + ConditionalGate, Q, S, C> ConditionalGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
+impl<L: Layer + ResetGate, Q, S, C> ResetGate for QubitSlotConvertLayer<L, Q, S, C>
+    where C: Converter<Q, L::Qubit, S, L::Slot> {}
 
-#[repr(transparent)]
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct QubitSlotConvertOperation<L: Layer, Q, S, C> (L::Operation, std::marker::PhantomData<(Q, S, C)>);
+transparent_wrapper! {
+    /// Operation of `QubitSlotConvertLayer`.
+    ///
+    /// This is only made from operation traits, so qubits and slots are always converted by `C`.
+    pub struct QubitSlotConvertOperation<L, Q, S, C>;
+}
 
-impl<L: Layer, Q, S, C> QubitSlotConvertOperation<L, Q, S, C>
-    where C: Converter<Q, L::Qubit, S, L::Slot>
-{
+impl<L: Layer, Q, S, C> QubitSlotConvertOperation<L, Q, S, C> {
     fn new(op: L::Operation) -> Self {
         Self(op, PhantomData)
     }
@@ -155,6 +195,104 @@ impl<L, Q, S, C> CXOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotCon
     }
 }
 
+impl<L, Q, S, C> RotationOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + RotationGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: RotationOperation<L>,
+{
+    fn rx(q: Q, theta: f64) -> Self {
+        Self::new(L::Operation::rx(C::qconv(q), theta))
+    }
+
+    fn ry(q: Q, theta: f64) -> Self {
+        Self::new(L::Operation::ry(C::qconv(q), theta))
+    }
+
+    fn rz(q: Q, theta: f64) -> Self {
+        Self::new(L::Operation::rz(C::qconv(q), theta))
+    }
+}
+
+impl<L, Q, S, C> U3Operation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + U3Gate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: U3Operation<L>,
+{
+    fn u3(q: Q, theta: f64, phi: f64, lambda: f64) -> Self {
+        Self::new(L::Operation::u3(C::qconv(q), theta, phi, lambda))
+    }
+}
+
+impl<L, Q, S, C> CZOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + CZGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: CZOperation<L>,
+{
+    fn cz(c: Q, t: Q) -> Self {
+        Self::new(L::Operation::cz(C::qconv(c), C::qconv(t)))
+    }
+}
+
+impl<L, Q, S, C> SwapOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + SwapGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: SwapOperation<L>,
+{
+    fn swap(a: Q, b: Q) -> Self {
+        Self::new(L::Operation::swap(C::qconv(a), C::qconv(b)))
+    }
+}
+
+impl<L, Q, S, C> CCXOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + CCXGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: CCXOperation<L>,
+{
+    fn ccx(c1: Q, c2: Q, t: Q) -> Self {
+        Self::new(L::Operation::ccx(C::qconv(c1), C::qconv(c2), C::qconv(t)))
+    }
+}
+
+impl<L, Q, S, C> CYOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + CYGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: CYOperation<L>,
+{
+    fn cy(c: Q, t: Q) -> Self {
+        Self::new(L::Operation::cy(C::qconv(c), C::qconv(t)))
+    }
+}
+
+impl<L, Q, S, C> ISwapOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + ISwapGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: ISwapOperation<L>,
+{
+    fn iswap(a: Q, b: Q) -> Self {
+        Self::new(L::Operation::iswap(C::qconv(a), C::qconv(b)))
+    }
+}
+
+impl<L, Q, S, C> ResetOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + ResetGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: ResetOperation<L>,
+{
+    fn reset(q: Q) -> Self {
+        Self::new(L::Operation::reset(C::qconv(q)))
+    }
+}
+
+impl<L, Q, S, C> ConditionalOperation<QubitSlotConvertLayer<L, Q, S, C>> for QubitSlotConvertOperation<L, Q, S, C>
+    where L: Layer + ConditionalGate,
+          C: Converter<Q, L::Qubit, S, L::Slot>,
+          <L as Layer>::Operation: ConditionalOperation<L>,
+{
+    fn if_slots(slots: Vec<S>, value: u64, op: Self) -> Self {
+        Self::new(L::Operation::if_slots(slots.into_iter().map(C::sconv).collect(), value, op.0))
+    }
+}
+
 #[repr(transparent)]
 pub struct QubitSlotConvertLayerBuffer<Conv, L: Layer, C> (L::Buffer, PhantomData<(Conv, C)>);
 
@@ -0,0 +1,96 @@
+//! Histogram of measured results.
+use std::collections::HashMap;
+use std::fmt;
+use num_traits::cast::NumCast;
+
+use crate::Measured;
+
+/// Counts of measured bitstrings over multiple shots.
+///
+/// A bitstring is stored as `u64` whose bit `i` is the result of `i`-th slot
+/// in the measured range, as `Measured::get_range_u64` returns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counts {
+    n_bits: usize,
+    shots: usize,
+    map: HashMap<u64, usize>,
+}
+
+impl Counts {
+    /// Makes empty counts for `n_bits` bits bitstrings.
+    pub fn new(n_bits: usize) -> Self {
+        assert!(n_bits <= 64, "Invalid number of bits.");
+        Counts { n_bits, shots: 0, map: HashMap::new() }
+    }
+
+    /// Adds a shot.
+    pub fn add(&mut self, bits: u64) {
+        *self.map.entry(bits).or_insert(0) += 1;
+        self.shots += 1;
+    }
+
+    /// Adds a shot from measured slots `start..start + n_bits`.
+    pub fn add_measured<M: Measured>(&mut self, measured: &M, start: usize)
+        where M::Slot: NumCast
+    {
+        self.add(measured.get_range_u64(start, start + self.n_bits));
+    }
+
+    /// Adds all shots of other counts.
+    pub fn merge(&mut self, other: &Counts) {
+        assert_eq!(self.n_bits, other.n_bits, "Number of bits mismatched.");
+        for (&bits, &n) in other.map.iter() {
+            *self.map.entry(bits).or_insert(0) += n;
+        }
+        self.shots += other.shots;
+    }
+
+    pub fn n_bits(&self) -> usize {
+        self.n_bits
+    }
+
+    /// Gets total number of shots.
+    pub fn shots(&self) -> usize {
+        self.shots
+    }
+
+    /// Gets the number of shots which the bitstring is measured.
+    pub fn get(&self, bits: u64) -> usize {
+        self.map.get(&bits).copied().unwrap_or(0)
+    }
+
+    /// Gets the frequency of the bitstring.
+    pub fn probability(&self, bits: u64) -> f64 {
+        if self.shots == 0 {
+            0.0
+        } else {
+            self.get(bits) as f64 / self.shots as f64
+        }
+    }
+
+    /// Gets the most frequently measured bitstring.
+    pub fn most_frequent(&self) -> Option<u64> {
+        self.map.iter().max_by_key(|&(&bits, &n)| (n, std::cmp::Reverse(bits))).map(|(&bits, _)| bits)
+    }
+
+    /// Iterates over measured bitstrings and their counts, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item=(u64, usize)> + '_ {
+        self.map.iter().map(|(&bits, &n)| (bits, n))
+    }
+
+    /// Formats the bitstring. The first slot is the rightmost character.
+    pub fn bitstring(&self, bits: u64) -> String {
+        (0..self.n_bits).rev().map(|i| if bits >> i & 1 == 1 { '1' } else { '0' }).collect()
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable();
+        for (bits, n) in entries {
+            writeln!(f, "{}: {}", self.bitstring(bits), n)?;
+        }
+        Ok(())
+    }
+}
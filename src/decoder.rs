@@ -0,0 +1,150 @@
+//! Decoders of detection events from QEC circuits.
+//!
+//! Detection events are given as node indices of a `MatchingGraph`.
+//! `SurfaceCode::matching_graph` makes the graph for surface code memory circuits,
+//! and `SurfaceCode::node` maps its detectors to the nodes.
+
+mod graph;
+pub use graph::{MatchingGraph, Edge};
+
+mod blossom;
+mod mwpm;
+pub use mwpm::MwpmDecoder;
+
+mod unionfind;
+pub use unionfind::UnionFindDecoder;
+
+/// Decoder which infers the logical correction from detection events.
+pub trait Decoder {
+    /// Returns whether the logical observable should be flipped.
+    ///
+    /// `events` are nodes of fired detectors. A node which appears twice cancels out.
+    /// Returns `None` if no set of edges explains the events.
+    fn decode(&self, events: &[usize]) -> Option<bool>;
+}
+
+/// Gets sorted nodes which appear odd times in `events`.
+fn defects(n_nodes: usize, events: &[usize]) -> Vec<usize> {
+    let mut odd = vec![false; n_nodes];
+    for &e in events {
+        assert!(e < n_nodes, "Node {} is out of range.", e);
+        odd[e] = !odd[e];
+    }
+    (0..n_nodes).filter(|&i| odd[i]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Layer, OpsVec, convert::SerializationLayer, sim::{StabilizerLayer, rng::Rng}, surface::SurfaceCode};
+
+    /// Finds the minimum weight set of edges which explains `events` by trying all sets.
+    fn brute_force(graph: &MatchingGraph, events: &[usize]) -> Option<(f64, bool)> {
+        let mut best: Option<(f64, bool)> = None;
+        for set in 0..1u32 << graph.edges().len() {
+            let mut odd = vec![false; graph.n_nodes()];
+            let (mut weight, mut flip) = (0.0, false);
+            for e in graph.edges().iter().enumerate().filter(|(i, _)| set >> i & 1 == 1).map(|(_, e)| e) {
+                odd[e.a] = !odd[e.a];
+                if let Some(b) = e.b {
+                    odd[b] = !odd[b];
+                }
+                weight += e.weight;
+                flip ^= e.observable;
+            }
+            if odd == (0..graph.n_nodes()).map(|i| events.contains(&i)).collect::<Vec<_>>()
+                    && best.is_none_or(|(w, _)| weight < w) {
+                best = Some((weight, flip));
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn random_graphs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..20 {
+            let n_nodes = 6;
+            let mut graph = MatchingGraph::new(n_nodes);
+            for _ in 0..9 {
+                let a = (rng.next_u64() % n_nodes as u64) as usize;
+                let b = (rng.next_u64() % (n_nodes as u64 + 1)) as usize;
+                let (weight, observable) = (0.5 + rng.next_f64(), rng.next_u64() & 1 == 1);
+                if b == n_nodes {
+                    graph.add_boundary_edge(a, weight, observable);
+                } else if a != b {
+                    graph.add_edge(a, b, weight, observable);
+                }
+            }
+            let mwpm = MwpmDecoder::new(graph.clone());
+            let uf = UnionFindDecoder::new(graph.clone());
+            for set in 0..1u32 << n_nodes {
+                let events: Vec<_> = (0..n_nodes).filter(|i| set >> i & 1 == 1).collect();
+                let expected = brute_force(&graph, &events);
+                let actual = mwpm.matching(&events);
+                assert_eq!(actual.is_some(), expected.is_some());
+                if let (Some((w1, f1)), Some((w2, f2))) = (actual, expected) {
+                    assert!((w1 - w2).abs() < 1e-4);
+                    assert_eq!(f1, f2);
+                }
+                assert_eq!(uf.decode(&events).is_some(), expected.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn infinite_weight() {
+        let mut graph = MatchingGraph::new(2);
+        graph.add_edge(0, 1, f64::INFINITY, false);
+        graph.add_boundary_edge(0, 1.0, true);
+        graph.add_boundary_edge(1, f64::INFINITY, false);
+        assert_eq!(graph.edges().len(), 1);
+        assert_eq!(MwpmDecoder::new(graph.clone()).decode(&[0]), Some(true));
+        assert_eq!(MwpmDecoder::new(graph.clone()).decode(&[0, 1]), None);
+        assert_eq!(UnionFindDecoder::new(graph).decode(&[1]), None);
+    }
+
+    #[test]
+    fn huge_weight() {
+        let mut graph = MatchingGraph::new(2);
+        graph.add_edge(0, 1, 3e20, false);
+        graph.add_boundary_edge(0, 1e20, true);
+        graph.add_boundary_edge(1, 1e20, false);
+        graph.add_boundary_edge(1, 1e300, false);
+        assert_eq!(MwpmDecoder::new(graph.clone()).decode(&[0, 1]), Some(true));
+        assert_eq!(UnionFindDecoder::new(graph).decode(&[0, 1]), Some(true));
+    }
+
+    #[test]
+    fn surface_code() {
+        type Surface = SerializationLayer<StabilizerLayer, 7>;
+        let code = SurfaceCode::new(3, 2);
+        let mwpm = MwpmDecoder::new(code.matching_graph());
+        let uf = UnionFindDecoder::new(code.matching_graph());
+        let mut layer = Surface::new(StabilizerLayer::with_seed(49, 0));
+        let mut buf = layer.make_buffer();
+        // Any single X error on a data qubit is corrected.
+        for &q in code.data_qubits() {
+            for at in 0..=code.rounds() {
+                let mut ops: OpsVec<Surface> = OpsVec::new();
+                ops.initialize();
+                for round in 0..code.rounds() {
+                    if round == at {
+                        ops.x(q);
+                    }
+                    code.syndrome_round(&mut ops, round);
+                }
+                if at == code.rounds() {
+                    ops.x(q);
+                }
+                code.measure_data(&mut ops);
+                layer.send_receive(ops.as_ref(), &mut buf);
+                let events: Vec<_> = code.detection_events(&buf).iter().filter_map(|d| code.node(d)).collect();
+                let observable = code.logical_observable(&buf);
+                assert_eq!(observable, q.1 == 1);
+                assert_eq!(mwpm.decode(&events), Some(observable));
+                assert_eq!(uf.decode(&events), Some(observable));
+            }
+        }
+    }
+}
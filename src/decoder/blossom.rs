@@ -0,0 +1,486 @@
+//! Maximum weight matching by Edmonds' blossom algorithm.
+//!
+//! This follows the O(n^3) implementation by Joris van Rantwijk, and always finds
+//! a matching of maximum cardinality. Edge weights are integers, so that dual variables
+//! are computed exactly.
+
+/// Finds a maximum weight matching among the maximum cardinality matchings.
+/// Returns the mate of each vertex.
+pub(super) fn max_weight_matching(n_vertices: usize, edges: &[(usize, usize, i64)]) -> Vec<Option<usize>> {
+    // Weights are doubled so that halving slacks always gives integers.
+    let edges: Vec<_> = edges.iter().map(|&(i, j, w)| (i, j, 2 * w)).collect();
+    let mut m = Matching::new(n_vertices, &edges);
+    m.solve();
+    m.mate.iter().map(|&p| if p >= 0 { Some(m.endpoint[p as usize]) } else { None }).collect()
+}
+
+/// States of the algorithm. Vertices are `0..n` and blossoms are `n..2n`.
+/// Endpoint `p` is the vertex `endpoint[p]` of the edge `p / 2`, and `-1` means none.
+/// Labels are 0 for free, 1 for S, 2 for T, and 5 is a temporary mark.
+struct Matching<'a> {
+    n: usize,
+    edges: &'a [(usize, usize, i64)],
+    endpoint: Vec<usize>,
+    neighbend: Vec<Vec<usize>>,
+    mate: Vec<isize>,
+    label: Vec<i8>,
+    labelend: Vec<isize>,
+    inblossom: Vec<usize>,
+    blossomparent: Vec<isize>,
+    blossomchilds: Vec<Vec<usize>>,
+    blossombase: Vec<isize>,
+    blossomendps: Vec<Vec<usize>>,
+    bestedge: Vec<isize>,
+    blossombestedges: Vec<Option<Vec<usize>>>,
+    unusedblossoms: Vec<usize>,
+    dualvar: Vec<i64>,
+    allowedge: Vec<bool>,
+    queue: Vec<usize>,
+}
+
+impl<'a> Matching<'a> {
+    fn new(n: usize, edges: &'a [(usize, usize, i64)]) -> Self {
+        let endpoint = edges.iter().flat_map(|&(i, j, _)| [i, j]).collect();
+        let mut neighbend = vec![vec![]; n];
+        for (k, &(i, j, _)) in edges.iter().enumerate() {
+            neighbend[i].push(2 * k + 1);
+            neighbend[j].push(2 * k);
+        }
+        let maxweight = edges.iter().map(|e| e.2).max().unwrap_or(0).max(0);
+        Matching {
+            n,
+            edges,
+            endpoint,
+            neighbend,
+            mate: vec![-1; n],
+            label: vec![0; 2 * n],
+            labelend: vec![-1; 2 * n],
+            inblossom: (0..n).collect(),
+            blossomparent: vec![-1; 2 * n],
+            blossomchilds: vec![vec![]; 2 * n],
+            blossombase: (0..n as isize).chain(std::iter::repeat_n(-1, n)).collect(),
+            blossomendps: vec![vec![]; 2 * n],
+            bestedge: vec![-1; 2 * n],
+            blossombestedges: vec![None; 2 * n],
+            unusedblossoms: (n..2 * n).collect(),
+            dualvar: std::iter::repeat_n(maxweight, n).chain(std::iter::repeat_n(0, n)).collect(),
+            allowedge: vec![false; edges.len()],
+            queue: vec![],
+        }
+    }
+
+    fn slack(&self, k: usize) -> i64 {
+        let (i, j, w) = self.edges[k];
+        self.dualvar[i] + self.dualvar[j] - 2 * w
+    }
+
+    fn leaves(&self, b: usize) -> Vec<usize> {
+        if b < self.n {
+            vec![b]
+        } else {
+            self.blossomchilds[b].iter().flat_map(|&t| self.leaves(t)).collect()
+        }
+    }
+
+    /// Labels the top blossom of `w` by `t` through the edge endpoint `p`.
+    fn assign_label(&mut self, w: usize, t: i8, p: isize) {
+        let b = self.inblossom[w];
+        self.label[w] = t;
+        self.label[b] = t;
+        self.labelend[w] = p;
+        self.labelend[b] = p;
+        self.bestedge[w] = -1;
+        self.bestedge[b] = -1;
+        if t == 1 {
+            let leaves = self.leaves(b);
+            self.queue.extend(leaves);
+        } else if t == 2 {
+            let base = self.blossombase[b] as usize;
+            let p = self.mate[base];
+            self.assign_label(self.endpoint[p as usize], 1, p ^ 1);
+        }
+    }
+
+    /// Traces back from S-vertices `v` and `w` to find a new blossom or an augmenting path.
+    /// Returns the base of the new blossom, or -1 for an augmenting path.
+    fn scan_blossom(&mut self, v: usize, w: usize) -> isize {
+        let mut path = vec![];
+        let mut base = -1;
+        let (mut v, mut w) = (v as isize, w as isize);
+        while v != -1 || w != -1 {
+            let b = self.inblossom[v as usize];
+            if self.label[b] & 4 != 0 {
+                base = self.blossombase[b];
+                break;
+            }
+            path.push(b);
+            self.label[b] = 5;
+            if self.labelend[b] == -1 {
+                v = -1;
+            } else {
+                let t = self.endpoint[self.labelend[b] as usize];
+                let b = self.inblossom[t];
+                v = self.endpoint[self.labelend[b] as usize] as isize;
+            }
+            if w != -1 {
+                std::mem::swap(&mut v, &mut w);
+            }
+        }
+        for b in path {
+            self.label[b] = 1;
+        }
+        base
+    }
+
+    /// Makes a new blossom with `base` through the edge `k` between S-vertices.
+    fn add_blossom(&mut self, base: usize, k: usize) {
+        let (mut v, mut w, _) = self.edges[k];
+        let bb = self.inblossom[base];
+        let mut bv = self.inblossom[v];
+        let mut bw = self.inblossom[w];
+        let b = self.unusedblossoms.pop().unwrap();
+        self.blossombase[b] = base as isize;
+        self.blossomparent[b] = -1;
+        self.blossomparent[bb] = b as isize;
+        let mut path = vec![];
+        let mut endps = vec![];
+        while bv != bb {
+            self.blossomparent[bv] = b as isize;
+            path.push(bv);
+            endps.push(self.labelend[bv] as usize);
+            v = self.endpoint[self.labelend[bv] as usize];
+            bv = self.inblossom[v];
+        }
+        path.push(bb);
+        path.reverse();
+        endps.reverse();
+        endps.push(2 * k);
+        while bw != bb {
+            self.blossomparent[bw] = b as isize;
+            path.push(bw);
+            endps.push((self.labelend[bw] ^ 1) as usize);
+            w = self.endpoint[self.labelend[bw] as usize];
+            bw = self.inblossom[w];
+        }
+        self.blossomchilds[b] = path.clone();
+        self.blossomendps[b] = endps;
+        self.label[b] = 1;
+        self.labelend[b] = self.labelend[bb];
+        self.dualvar[b] = 0;
+        for v in self.leaves(b) {
+            if self.label[self.inblossom[v]] == 2 {
+                self.queue.push(v);
+            }
+            self.inblossom[v] = b;
+        }
+        // Computes the least-slack edges to neighboring S-blossoms.
+        let mut bestedgeto = vec![-1isize; 2 * self.n];
+        for &bv in &path {
+            let nblist: Vec<usize> = match self.blossombestedges[bv].take() {
+                Some(list) => list,
+                None => self.leaves(bv).into_iter().flat_map(|v| self.neighbend[v].iter().map(|p| p / 2)).collect(),
+            };
+            for k in nblist {
+                let (mut i, mut j, _) = self.edges[k];
+                if self.inblossom[j] == b {
+                    std::mem::swap(&mut i, &mut j);
+                }
+                let bj = self.inblossom[j];
+                if bj != b && self.label[bj] == 1
+                        && (bestedgeto[bj] == -1 || self.slack(k) < self.slack(bestedgeto[bj] as usize)) {
+                    bestedgeto[bj] = k as isize;
+                }
+            }
+            self.bestedge[bv] = -1;
+        }
+        let best: Vec<usize> = bestedgeto.into_iter().filter(|&k| k != -1).map(|k| k as usize).collect();
+        self.bestedge[b] = -1;
+        for &k in &best {
+            if self.bestedge[b] == -1 || self.slack(k) < self.slack(self.bestedge[b] as usize) {
+                self.bestedge[b] = k as isize;
+            }
+        }
+        self.blossombestedges[b] = Some(best);
+    }
+
+    /// Expands the blossom `b`. At the end of a stage, sub-blossoms with zero dual are also expanded.
+    fn expand_blossom(&mut self, b: usize, endstage: bool) {
+        let childs = self.blossomchilds[b].clone();
+        for &s in &childs {
+            self.blossomparent[s] = -1;
+            if s < self.n {
+                self.inblossom[s] = s;
+            } else if endstage && self.dualvar[s] == 0 {
+                self.expand_blossom(s, endstage);
+            } else {
+                for v in self.leaves(s) {
+                    self.inblossom[v] = s;
+                }
+            }
+        }
+        if !endstage && self.label[b] == 2 {
+            // Relabels the sub-blossoms on the even path from the entry child to the base.
+            let endps = self.blossomendps[b].clone();
+            let len = childs.len() as isize;
+            let at = |j: isize| j.rem_euclid(len) as usize;
+            let entrychild = self.inblossom[self.endpoint[(self.labelend[b] ^ 1) as usize]];
+            let mut j = childs.iter().position(|&c| c == entrychild).unwrap() as isize;
+            let (jstep, endptrick) = if j & 1 == 1 {
+                j -= len;
+                (1, 0)
+            } else {
+                (-1, 1)
+            };
+            let mut p = self.labelend[b] as usize;
+            while j != 0 {
+                self.label[self.endpoint[p ^ 1]] = 0;
+                self.label[self.endpoint[endps[at(j - endptrick)] ^ endptrick as usize ^ 1]] = 0;
+                self.assign_label(self.endpoint[p ^ 1], 2, p as isize);
+                self.allowedge[endps[at(j - endptrick)] / 2] = true;
+                j += jstep;
+                p = endps[at(j - endptrick)] ^ endptrick as usize;
+                self.allowedge[p / 2] = true;
+                j += jstep;
+            }
+            let bv = childs[at(j)];
+            self.label[self.endpoint[p ^ 1]] = 2;
+            self.label[bv] = 2;
+            self.labelend[self.endpoint[p ^ 1]] = p as isize;
+            self.labelend[bv] = p as isize;
+            self.bestedge[bv] = -1;
+            j += jstep;
+            while childs[at(j)] != entrychild {
+                let bv = childs[at(j)];
+                if self.label[bv] == 1 {
+                    j += jstep;
+                    continue;
+                }
+                if let Some(v) = self.leaves(bv).into_iter().find(|&v| self.label[v] != 0) {
+                    self.label[v] = 0;
+                    let base = self.blossombase[bv] as usize;
+                    self.label[self.endpoint[self.mate[base] as usize]] = 0;
+                    self.assign_label(v, 2, self.labelend[v]);
+                }
+                j += jstep;
+            }
+        }
+        self.label[b] = -1;
+        self.labelend[b] = -1;
+        self.blossomchilds[b].clear();
+        self.blossomendps[b].clear();
+        self.blossombase[b] = -1;
+        self.blossombestedges[b] = None;
+        self.bestedge[b] = -1;
+        self.unusedblossoms.push(b);
+    }
+
+    /// Swaps matched and unmatched edges in the blossom `b` so that `v` becomes its base.
+    fn augment_blossom(&mut self, b: usize, v: usize) {
+        let mut t = v;
+        while self.blossomparent[t] != b as isize {
+            t = self.blossomparent[t] as usize;
+        }
+        if t >= self.n {
+            self.augment_blossom(t, v);
+        }
+        let childs = self.blossomchilds[b].clone();
+        let endps = self.blossomendps[b].clone();
+        let len = childs.len() as isize;
+        let at = |j: isize| j.rem_euclid(len) as usize;
+        let i = childs.iter().position(|&c| c == t).unwrap();
+        let mut j = i as isize;
+        let (jstep, endptrick) = if i & 1 == 1 {
+            j -= len;
+            (1, 0)
+        } else {
+            (-1, 1)
+        };
+        while j != 0 {
+            j += jstep;
+            let t = childs[at(j)];
+            let p = endps[at(j - endptrick)] ^ endptrick as usize;
+            if t >= self.n {
+                self.augment_blossom(t, self.endpoint[p]);
+            }
+            j += jstep;
+            let t = childs[at(j)];
+            if t >= self.n {
+                self.augment_blossom(t, self.endpoint[p ^ 1]);
+            }
+            self.mate[self.endpoint[p]] = (p ^ 1) as isize;
+            self.mate[self.endpoint[p ^ 1]] = p as isize;
+        }
+        self.blossomchilds[b].rotate_left(i);
+        self.blossomendps[b].rotate_left(i);
+        self.blossombase[b] = self.blossombase[self.blossomchilds[b][0]];
+    }
+
+    /// Augments the matching along the path through the edge `k` between S-vertices.
+    fn augment_matching(&mut self, k: usize) {
+        let (v, w, _) = self.edges[k];
+        for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+            loop {
+                let bs = self.inblossom[s];
+                if bs >= self.n {
+                    self.augment_blossom(bs, s);
+                }
+                self.mate[s] = p as isize;
+                if self.labelend[bs] == -1 {
+                    break;
+                }
+                let t = self.endpoint[self.labelend[bs] as usize];
+                let bt = self.inblossom[t];
+                s = self.endpoint[self.labelend[bt] as usize];
+                let j = self.endpoint[(self.labelend[bt] ^ 1) as usize];
+                if bt >= self.n {
+                    self.augment_blossom(bt, j);
+                }
+                self.mate[j] = self.labelend[bt];
+                p = (self.labelend[bt] ^ 1) as usize;
+            }
+        }
+    }
+
+    fn solve(&mut self) {
+        let n = self.n;
+        for _ in 0..n {
+            // Starts a stage, which finds an augmenting path.
+            self.label.iter_mut().for_each(|l| *l = 0);
+            self.bestedge.iter_mut().for_each(|e| *e = -1);
+            self.blossombestedges[n..].iter_mut().for_each(|e| *e = None);
+            self.allowedge.iter_mut().for_each(|a| *a = false);
+            self.queue.clear();
+            for v in 0..n {
+                if self.mate[v] == -1 && self.label[self.inblossom[v]] == 0 {
+                    self.assign_label(v, 1, -1);
+                }
+            }
+            let mut augmented = false;
+            loop {
+                while !augmented {
+                    let Some(v) = self.queue.pop() else { break };
+                    for idx in 0..self.neighbend[v].len() {
+                        let p = self.neighbend[v][idx];
+                        let k = p / 2;
+                        let w = self.endpoint[p];
+                        if self.inblossom[v] == self.inblossom[w] {
+                            continue;
+                        }
+                        let mut kslack = 0;
+                        if !self.allowedge[k] {
+                            kslack = self.slack(k);
+                            if kslack <= 0 {
+                                self.allowedge[k] = true;
+                            }
+                        }
+                        if self.allowedge[k] {
+                            if self.label[self.inblossom[w]] == 0 {
+                                self.assign_label(w, 2, (p ^ 1) as isize);
+                            } else if self.label[self.inblossom[w]] == 1 {
+                                let base = self.scan_blossom(v, w);
+                                if base >= 0 {
+                                    self.add_blossom(base as usize, k);
+                                } else {
+                                    self.augment_matching(k);
+                                    augmented = true;
+                                    break;
+                                }
+                            } else if self.label[w] == 0 {
+                                self.label[w] = 2;
+                                self.labelend[w] = (p ^ 1) as isize;
+                            }
+                        } else if self.label[self.inblossom[w]] == 1 {
+                            let b = self.inblossom[v];
+                            if self.bestedge[b] == -1 || kslack < self.slack(self.bestedge[b] as usize) {
+                                self.bestedge[b] = k as isize;
+                            }
+                        } else if self.label[w] == 0
+                                && (self.bestedge[w] == -1 || kslack < self.slack(self.bestedge[w] as usize)) {
+                            self.bestedge[w] = k as isize;
+                        }
+                    }
+                }
+                if augmented {
+                    break;
+                }
+
+                // Updates dual variables by the largest possible delta.
+                let mut deltatype = -1;
+                let (mut delta, mut deltaedge, mut deltablossom) = (0, 0, 0);
+                for v in 0..n {
+                    if self.label[self.inblossom[v]] == 0 && self.bestedge[v] != -1 {
+                        let d = self.slack(self.bestedge[v] as usize);
+                        if deltatype == -1 || d < delta {
+                            delta = d;
+                            deltatype = 2;
+                            deltaedge = self.bestedge[v] as usize;
+                        }
+                    }
+                }
+                for b in 0..2 * n {
+                    if self.blossomparent[b] == -1 && self.label[b] == 1 && self.bestedge[b] != -1 {
+                        let d = self.slack(self.bestedge[b] as usize) / 2;
+                        if deltatype == -1 || d < delta {
+                            delta = d;
+                            deltatype = 3;
+                            deltaedge = self.bestedge[b] as usize;
+                        }
+                    }
+                }
+                for b in n..2 * n {
+                    if self.blossombase[b] >= 0 && self.blossomparent[b] == -1 && self.label[b] == 2
+                            && (deltatype == -1 || self.dualvar[b] < delta) {
+                        delta = self.dualvar[b];
+                        deltatype = 4;
+                        deltablossom = b;
+                    }
+                }
+                if deltatype == -1 {
+                    // No further improvement is possible.
+                    deltatype = 1;
+                    delta = self.dualvar[..n].iter().copied().min().unwrap_or(0).max(0);
+                }
+                for v in 0..n {
+                    match self.label[self.inblossom[v]] {
+                        1 => self.dualvar[v] -= delta,
+                        2 => self.dualvar[v] += delta,
+                        _ => {},
+                    }
+                }
+                for b in n..2 * n {
+                    if self.blossombase[b] >= 0 && self.blossomparent[b] == -1 {
+                        match self.label[b] {
+                            1 => self.dualvar[b] += delta,
+                            2 => self.dualvar[b] -= delta,
+                            _ => {},
+                        }
+                    }
+                }
+                match deltatype {
+                    1 => break,
+                    2 => {
+                        self.allowedge[deltaedge] = true;
+                        let (mut i, j, _) = self.edges[deltaedge];
+                        if self.label[self.inblossom[i]] == 0 {
+                            i = j;
+                        }
+                        self.queue.push(i);
+                    },
+                    3 => {
+                        self.allowedge[deltaedge] = true;
+                        self.queue.push(self.edges[deltaedge].0);
+                    },
+                    _ => self.expand_blossom(deltablossom, false),
+                }
+            }
+            if !augmented {
+                break;
+            }
+            for b in n..2 * n {
+                if self.blossomparent[b] == -1 && self.blossombase[b] >= 0 && self.label[b] == 1 && self.dualvar[b] == 0 {
+                    self.expand_blossom(b, true);
+                }
+            }
+        }
+    }
+}
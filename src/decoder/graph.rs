@@ -0,0 +1,78 @@
+/// Edge of `MatchingGraph`, which is an error mechanism flipping detectors at its ends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub a: usize,
+    /// The other node, or `None` for an edge to the boundary.
+    pub b: Option<usize>,
+    /// Weight, which is usually `ln((1 - p) / p)` for the error probability `p`.
+    pub weight: f64,
+    /// Whether the error flips the logical observable.
+    pub observable: bool,
+}
+
+/// Graph whose nodes are detectors and edges are errors.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchingGraph {
+    n_nodes: usize,
+    edges: Vec<Edge>,
+}
+
+impl MatchingGraph {
+    /// Makes a graph with `n_nodes` nodes and no edges.
+    pub fn new(n_nodes: usize) -> Self {
+        MatchingGraph { n_nodes, edges: vec![] }
+    }
+
+    pub fn n_nodes(&self) -> usize {
+        self.n_nodes
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Adds an edge between nodes `a` and `b`.
+    ///
+    /// Edges of infinite weight are impossible errors, and they are left out.
+    pub fn add_edge(&mut self, a: usize, b: usize, weight: f64, observable: bool) {
+        assert!(b < self.n_nodes && a != b, "Invalid node {}.", b);
+        self.push(Edge { a, b: Some(b), weight, observable });
+    }
+
+    /// Adds an edge between node `a` and the boundary.
+    ///
+    /// Edges of infinite weight are left out as `add_edge`.
+    pub fn add_boundary_edge(&mut self, a: usize, weight: f64, observable: bool) {
+        self.push(Edge { a, b: None, weight, observable });
+    }
+
+    fn push(&mut self, edge: Edge) {
+        assert!(edge.a < self.n_nodes, "Invalid node {}.", edge.a);
+        assert!(edge.weight >= 0.0, "Weight should not be negative or NaN.");
+        if edge.weight.is_finite() {
+            self.edges.push(edge);
+        }
+    }
+
+    /// Gets the boundary as node `n_nodes`.
+    pub(super) fn boundary(&self) -> usize {
+        self.n_nodes
+    }
+
+    /// Gets the other end of the edge, where the boundary is `n_nodes`.
+    pub(super) fn other(&self, edge: usize, node: usize) -> usize {
+        let e = &self.edges[edge];
+        let b = e.b.unwrap_or(self.n_nodes);
+        if e.a == node { b } else { e.a }
+    }
+
+    /// Gets edge indices for each node including the boundary.
+    pub(super) fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adj = vec![vec![]; self.n_nodes + 1];
+        for (i, e) in self.edges.iter().enumerate() {
+            adj[e.a].push(i);
+            adj[e.b.unwrap_or(self.n_nodes)].push(i);
+        }
+        adj
+    }
+}
@@ -0,0 +1,128 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::{Decoder, MatchingGraph, blossom, defects};
+
+/// Resolution of path weights in the matching, which uses integer weights.
+const RESOLUTION: f64 = 1e-6;
+
+/// Largest integer weight in the matching. Heavier paths are scaled down to this, losing
+/// resolution, so that the blossom algorithm doesn't overflow.
+const MAX_INT_WEIGHT: f64 = (1u64 << 40) as f64;
+
+/// Minimum-weight perfect matching decoder.
+///
+/// Detection events are matched to each other or to the boundary along the shortest paths,
+/// so that the total weight is minimized. This is exact, but takes O(n^3) time
+/// for n events.
+#[derive(Debug, Clone)]
+pub struct MwpmDecoder {
+    graph: MatchingGraph,
+    adjacency: Vec<Vec<usize>>,
+}
+
+/// Entry of Dijkstra's priority queue, ordered by the smallest distance first.
+#[derive(PartialEq)]
+struct Entry(f64, usize);
+
+impl Eq for Entry {}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl MwpmDecoder {
+    pub fn new(graph: MatchingGraph) -> Self {
+        let adjacency = graph.adjacency();
+        MwpmDecoder { graph, adjacency }
+    }
+
+    pub fn graph(&self) -> &MatchingGraph {
+        &self.graph
+    }
+
+    /// Gets the distances and the observable parities of the shortest paths from `from`.
+    fn shortest_paths(&self, from: usize) -> Vec<Option<(f64, bool)>> {
+        let mut paths = vec![None; self.graph.n_nodes() + 1];
+        let mut done = vec![false; paths.len()];
+        let mut heap = BinaryHeap::new();
+        paths[from] = Some((0.0, false));
+        heap.push(Entry(0.0, from));
+        while let Some(Entry(dist, v)) = heap.pop() {
+            if done[v] {
+                continue;
+            }
+            done[v] = true;
+            let parity = paths[v].unwrap().1;
+            for &e in &self.adjacency[v] {
+                let edge = &self.graph.edges()[e];
+                let w = self.graph.other(e, v);
+                let d = dist + edge.weight;
+                if !done[w] && paths[w].is_none_or(|(d0, _)| d < d0) {
+                    paths[w] = Some((d, parity ^ edge.observable));
+                    heap.push(Entry(d, w));
+                }
+            }
+        }
+        paths
+    }
+
+    /// Finds the matching of defects, and returns its weight and the observable parity.
+    pub(super) fn matching(&self, defects: &[usize]) -> Option<(f64, bool)> {
+        let k = defects.len();
+        let paths: Vec<_> = defects.iter().map(|&d| self.shortest_paths(d)).collect();
+        // Defects are vertices 0..k and their boundary copies are k..2k.
+        // Each defect is matched to another defect, or to its own boundary copy.
+        // Unused boundary copies are matched to each other by zero-weight edges.
+        // Paths of infinite weight, which are sums of huge weights, are left out.
+        let boundary = self.graph.boundary();
+        let mut costs = vec![];
+        for (i, path) in paths.iter().enumerate() {
+            for j in i + 1..k {
+                if let Some((w, _)) = path[defects[j]].filter(|p| p.0.is_finite()) {
+                    costs.push((i, j, w));
+                }
+            }
+            if let Some((w, _)) = path[boundary].filter(|p| p.0.is_finite()) {
+                costs.push((i, k + i, w));
+            }
+        }
+        let heaviest = costs.iter().map(|c| c.2).fold(0.0, f64::max);
+        let scale = (1.0 / RESOLUTION).min(MAX_INT_WEIGHT / heaviest);
+        let costs: Vec<_> = costs.into_iter().map(|(i, j, w)| (i, j, (w * scale).round() as i64)).collect();
+        let max = costs.iter().map(|c| c.2).max().unwrap_or(0) + 1;
+        let mut edges: Vec<_> = costs.into_iter().map(|(i, j, c)| (i, j, max - c)).collect();
+        for i in 0..k {
+            for j in i + 1..k {
+                edges.push((k + i, k + j, max));
+            }
+        }
+        let mates = blossom::max_weight_matching(2 * k, &edges);
+
+        let (mut weight, mut flip) = (0.0, false);
+        for i in 0..k {
+            let (w, f) = match mates[i]? {
+                j if j == k + i => paths[i][boundary]?,
+                j if j < i => continue,
+                j => paths[i][defects[j]]?,
+            };
+            weight += w;
+            flip ^= f;
+        }
+        Some((weight, flip))
+    }
+}
+
+impl Decoder for MwpmDecoder {
+    fn decode(&self, events: &[usize]) -> Option<bool> {
+        self.matching(&defects(self.graph.n_nodes(), events)).map(|(_, flip)| flip)
+    }
+}
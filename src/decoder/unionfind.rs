@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+use super::{Decoder, MatchingGraph, defects};
+
+/// Union-Find decoder, which is faster than `MwpmDecoder` but may find a heavier correction.
+///
+/// Clusters around detection events grow along edges by their weights until every cluster
+/// has even number of events or touches the boundary, and then the correction is peeled
+/// from spanning trees of the clusters.
+#[derive(Debug, Clone)]
+pub struct UnionFindDecoder {
+    graph: MatchingGraph,
+    adjacency: Vec<Vec<usize>>,
+}
+
+/// Disjoint sets of nodes. The parity, the boundary flag and the edges which may leave
+/// the cluster are kept in the roots.
+struct Clusters {
+    parent: Vec<usize>,
+    odd: Vec<bool>,
+    boundary: Vec<bool>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl Clusters {
+    fn find(&mut self, v: usize) -> usize {
+        let mut root = v;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut v = v;
+        while self.parent[v] != root {
+            let next = self.parent[v];
+            self.parent[v] = root;
+            v = next;
+        }
+        root
+    }
+
+    /// Merges the clusters, keeping the root with more edges so that each edge is moved few times.
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a != b {
+            if self.edges[a].len() < self.edges[b].len() {
+                std::mem::swap(&mut a, &mut b);
+            }
+            self.parent[b] = a;
+            self.odd[a] ^= self.odd[b];
+            self.boundary[a] |= self.boundary[b];
+            let edges = std::mem::take(&mut self.edges[b]);
+            self.edges[a].extend(edges);
+        }
+    }
+
+    /// Whether the cluster of `v` should grow.
+    fn is_active(&mut self, v: usize) -> bool {
+        let root = self.find(v);
+        self.odd[root] && !self.boundary[root]
+    }
+}
+
+impl UnionFindDecoder {
+    pub fn new(graph: MatchingGraph) -> Self {
+        let adjacency = graph.adjacency();
+        UnionFindDecoder { graph, adjacency }
+    }
+
+    pub fn graph(&self) -> &MatchingGraph {
+        &self.graph
+    }
+
+    /// Grows clusters until no cluster is active, and returns the fully grown edges.
+    ///
+    /// Only the edges which may leave active clusters are visited in each step.
+    fn grow(&self, syndrome: &[bool]) -> Option<Vec<bool>> {
+        let n = self.graph.n_nodes() + 1;
+        let boundary = self.graph.boundary();
+        let mut clusters = Clusters {
+            parent: (0..n).collect(),
+            odd: syndrome.to_vec(),
+            boundary: (0..n).map(|v| v == boundary).collect(),
+            edges: self.adjacency.clone(),
+        };
+        let edges = self.graph.edges();
+        let mut growth = vec![0.0; edges.len()];
+        let mut grown = vec![false; edges.len()];
+        let mut visited = vec![usize::MAX; edges.len()];
+        let mut active: Vec<_> = (0..n).filter(|&v| clusters.is_active(v)).collect();
+        for step in 0.. {
+            // Edges grow from each active end at the same speed.
+            let mut frontier = vec![];
+            for &root in &active {
+                let mut cluster_edges = std::mem::take(&mut clusters.edges[root]);
+                cluster_edges.retain(|&i| {
+                    let b = edges[i].b.unwrap_or(boundary);
+                    !grown[i] && clusters.find(edges[i].a) != clusters.find(b)
+                });
+                for &i in &cluster_edges {
+                    if visited[i] == step {
+                        continue;
+                    }
+                    visited[i] = step;
+                    let rate = clusters.is_active(edges[i].a) as u32 +
+                               clusters.is_active(edges[i].b.unwrap_or(boundary)) as u32;
+                    frontier.push((i, rate as f64, (edges[i].weight - growth[i]) / rate as f64));
+                }
+                clusters.edges[root] = cluster_edges;
+            }
+            if frontier.is_empty() {
+                break;
+            }
+            let delta = frontier.iter().map(|f| f.2).fold(f64::INFINITY, f64::min);
+            let mut fused = vec![];
+            for (i, rate, remaining) in frontier {
+                if remaining <= delta {
+                    grown[i] = true;
+                    fused.push(i);
+                } else {
+                    growth[i] += delta * rate;
+                }
+            }
+            for i in fused {
+                clusters.union(edges[i].a, edges[i].b.unwrap_or(boundary));
+            }
+            // Clusters only become active by merging with active clusters.
+            let mut roots: Vec<_> = active.iter().map(|&v| clusters.find(v)).collect();
+            roots.sort_unstable();
+            roots.dedup();
+            roots.retain(|&v| clusters.is_active(v));
+            active = roots;
+        }
+        if !active.is_empty() {
+            return None;
+        }
+        Some(grown)
+    }
+
+    /// Peels the correction from spanning trees of grown edges, and returns the observable parity.
+    fn peel(&self, syndrome: &mut [bool], grown: &[bool]) -> bool {
+        let boundary = self.graph.boundary();
+        let mut visited = vec![false; syndrome.len()];
+        let mut parent_edge = vec![None; syndrome.len()];
+        let mut order = vec![];
+        // Trees touching the boundary are rooted at the boundary, which absorbs any parity.
+        for root in std::iter::once(boundary).chain(0..boundary) {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            let mut queue = VecDeque::from([root]);
+            while let Some(v) = queue.pop_front() {
+                order.push(v);
+                for &e in self.adjacency[v].iter().filter(|&&e| grown[e]) {
+                    let w = self.graph.other(e, v);
+                    if !visited[w] {
+                        visited[w] = true;
+                        parent_edge[w] = Some(e);
+                        queue.push_back(w);
+                    }
+                }
+            }
+        }
+        let mut flip = false;
+        for &v in order.iter().rev() {
+            if let (true, Some(e)) = (syndrome[v], parent_edge[v]) {
+                syndrome[v] = false;
+                let parent = self.graph.other(e, v);
+                syndrome[parent] ^= parent != boundary;
+                flip ^= self.graph.edges()[e].observable;
+            }
+        }
+        flip
+    }
+}
+
+impl Decoder for UnionFindDecoder {
+    fn decode(&self, events: &[usize]) -> Option<bool> {
+        let mut syndrome = vec![false; self.graph.n_nodes() + 1];
+        for v in defects(self.graph.n_nodes(), events) {
+            syndrome[v] = true;
+        }
+        let grown = self.grow(&syndrome)?;
+        Some(self.peel(&mut syndrome, &grown))
+    }
+}
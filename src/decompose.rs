@@ -0,0 +1,294 @@
+//! Layer which lowers gates into the native basis of the inner layer.
+use std::collections::HashSet;
+use std::f64::consts::FRAC_PI_4;
+
+use crate::{Error, Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs}};
+
+/// Layer which provides all builtin gates on top of a layer which has H, T and CX gates.
+///
+/// Operations which are not in the native set are rewritten by standard decompositions.
+/// Decompositions may differ from the original gate by global phase.
+/// H, T, T†, CX, initialize and measurement are always native. Other operation IDs,
+/// including user defined ones, are sent as is only when they are added to the native set.
+/// Reset is sent as is, and this layer implements `ResetGate` only when the inner layer does.
+///
+/// Conditional operations are lowered into conditional native operations, and this layer
+/// implements `ConditionalGate` only when the inner layer does.
+///
+/// Rotation angles are continuous, so rotations are lowered into inner RZ when RZ is native.
+/// Otherwise only angles of multiples of π/4 can be lowered, and the other angles are reported as errors.
+#[derive(Debug)]
+pub struct DecomposeLayer<L> {
+    layer: L,
+    native: HashSet<u16>,
+}
+
+const BASIS: [u16; 6] = [opid::INIT, opid::MEAS, opid::H, opid::T, opid::TDG, opid::CX];
+
+impl<L> DecomposeLayer<L>
+    where L: Layer<Operation=OpArgs<L>> + HGate + TGate + CXGate,
+          L::Qubit: Clone,
+          L::Slot: Clone,
+{
+    /// Makes a layer whose native set is H, T, T† and CX.
+    pub fn new(layer: L) -> Self {
+        Self::with_native(layer, vec![])
+    }
+
+    /// Makes a layer whose native set has given operation IDs in addition to H, T, T† and CX.
+    pub fn with_native<I: IntoIterator<Item=u16>>(layer: L, native: I) -> Self {
+        let mut layer = DecomposeLayer { layer, native: HashSet::new() };
+        layer.set_native(native);
+        layer
+    }
+
+    /// Replaces the native set. H, T, T† and CX are always included.
+    pub fn set_native<I: IntoIterator<Item=u16>>(&mut self, native: I) {
+        self.native = BASIS.iter().copied().chain(native).collect();
+    }
+
+    pub fn native(&self) -> &HashSet<u16> {
+        &self.native
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.layer
+    }
+
+    pub fn into_inner(self) -> L {
+        self.layer
+    }
+
+    /// Rewrites operations into the native set.
+    pub fn decompose(&self, ops: &[OpArgs<Self>]) -> Result<Vec<OpArgs<L>>, Error> {
+        let mut lowered = Vec::with_capacity(ops.len());
+        for op in ops {
+            self.lower(op.try_clone().ok_or(Error::UnsupportedOperation(op.id()))?.cast(), &mut lowered)?;
+        }
+        Ok(lowered)
+    }
+
+    fn lower(&self, op: OpArgs<L>, out: &mut Vec<OpArgs<L>>) -> Result<(), Error> {
+        use OpArgs::{Q, QD, QQ};
+        if let OpArgs::If(id, slots, value, op) = op {
+            let mut lowered = vec![];
+            self.lower(*op, &mut lowered)?;
+            out.extend(lowered.into_iter().map(|op| OpArgs::If(id, slots.clone(), value, Box::new(op))));
+            return Ok(());
+        }
+        if self.native.contains(&op.id()) || op.id() == opid::RESET {
+            out.push(op);
+            return Ok(());
+        }
+        let ops = match op {
+            Q(opid::X, q) => vec![Q(opid::H, q.clone()), Q(opid::Z, q.clone()), Q(opid::H, q)],
+            Q(opid::Y, q) => vec![Q(opid::Z, q.clone()), Q(opid::X, q)],
+            Q(opid::Z, q) => vec![Q(opid::S, q.clone()), Q(opid::S, q)],
+            Q(opid::S, q) => vec![Q(opid::T, q.clone()), Q(opid::T, q)],
+            Q(opid::SDG, q) => vec![Q(opid::TDG, q.clone()), Q(opid::TDG, q)],
+            QQ(opid::CZ, c, t) => vec![Q(opid::H, t.clone()), QQ(opid::CX, c, t.clone()), Q(opid::H, t)],
+            QQ(opid::CY, c, t) => vec![Q(opid::SDG, t.clone()), QQ(opid::CX, c, t.clone()), Q(opid::S, t)],
+            QQ(opid::SWAP, a, b) => vec![QQ(opid::CX, a.clone(), b.clone()), QQ(opid::CX, b.clone(), a.clone()),
+                                         QQ(opid::CX, a, b)],
+            QQ(opid::ISWAP, a, b) => vec![Q(opid::S, a.clone()), Q(opid::S, b.clone()), Q(opid::H, a.clone()),
+                                          QQ(opid::CX, a.clone(), b.clone()), QQ(opid::CX, b.clone(), a),
+                                          Q(opid::H, b)],
+            OpArgs::QQQ(opid::CCX, a, b, c) => vec![
+                Q(opid::H, c.clone()), QQ(opid::CX, b.clone(), c.clone()), Q(opid::TDG, c.clone()),
+                QQ(opid::CX, a.clone(), c.clone()), Q(opid::T, c.clone()), QQ(opid::CX, b.clone(), c.clone()),
+                Q(opid::TDG, c.clone()), QQ(opid::CX, a.clone(), c.clone()), Q(opid::T, b.clone()),
+                Q(opid::T, c.clone()), Q(opid::H, c), QQ(opid::CX, a.clone(), b.clone()),
+                Q(opid::T, a.clone()), Q(opid::TDG, b.clone()), QQ(opid::CX, a, b),
+            ],
+            QD(opid::RZ, q, theta) => {
+                let k = theta / FRAC_PI_4;
+                if (k - k.round()).abs() > 1e-9 {
+                    return Err(Error::InvalidOperation(
+                        format!("RZ({}) is not a multiple of π/4 and RZ is not native", theta)));
+                }
+                let ids: &[u16] = match (k.round() as i64).rem_euclid(8) {
+                    0 => &[],
+                    1 => &[opid::T],
+                    2 => &[opid::S],
+                    3 => &[opid::S, opid::T],
+                    4 => &[opid::Z],
+                    5 => &[opid::Z, opid::T],
+                    6 => &[opid::SDG],
+                    _ => &[opid::TDG],
+                };
+                ids.iter().map(|&id| Q(id, q.clone())).collect()
+            },
+            QD(opid::RX, q, theta) => vec![Q(opid::H, q.clone()), QD(opid::RZ, q.clone(), theta), Q(opid::H, q)],
+            QD(opid::RY, q, theta) => vec![Q(opid::SDG, q.clone()), QD(opid::RX, q.clone(), theta), Q(opid::S, q)],
+            OpArgs::QDDD(opid::U3, q, theta, phi, lambda) => vec![QD(opid::RZ, q.clone(), lambda),
+                                                                  QD(opid::RY, q.clone(), theta),
+                                                                  QD(opid::RZ, q, phi)],
+            op => return Err(Error::UnsupportedOperation(op.id())),
+        };
+        for op in ops {
+            self.lower(op, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl<L> Layer for DecomposeLayer<L>
+    where L: Layer<Operation=OpArgs<L>> + HGate + TGate + CXGate,
+          L::Qubit: Clone,
+          L::Slot: Clone,
+{
+    type Operation = OpArgs<Self>;
+    type Qubit = L::Qubit;
+    type Slot = L::Slot;
+    type Buffer = L::Buffer;
+    type Requested = L::Requested;
+    type Response = L::Response;
+
+    fn make_buffer(&self) -> L::Buffer {
+        self.layer.make_buffer()
+    }
+
+    /// Sends decomposed operations.
+    ///
+    /// # Panics
+    /// Panics before sending anything when any operation can't be decomposed,
+    /// such as a rotation which is not a multiple of π/4 while RZ is not native.
+    /// Use `try_send` or `decompose` to get the error instead.
+    fn send(&mut self, ops: &[Self::Operation]) -> L::Requested {
+        let ops = self.decompose(ops).unwrap_or_else(|e| panic!("{}", e));
+        self.layer.send(&ops)
+    }
+
+    fn receive(&mut self, buf: &mut L::Buffer) -> L::Response {
+        self.layer.receive(buf)
+    }
+
+    /// Sends decomposed operations and receives the result.
+    ///
+    /// # Panics
+    /// Panics before sending anything when any operation can't be decomposed, as `send` does.
+    fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> L::Response {
+        let ops = self.decompose(ops).unwrap_or_else(|e| panic!("{}", e));
+        self.layer.send_receive(&ops, buf)
+    }
+}
+
+impl<L> TryLayer for DecomposeLayer<L>
+    where L: TryLayer<Operation=OpArgs<L>> + HGate + TGate + CXGate,
+          L::Qubit: Clone,
+          L::Slot: Clone,
+{
+    /// Sends operations. Nothing is sent when any operation can't be decomposed.
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<L::Requested, Error> {
+        let ops = self.decompose(ops)?;
+        self.layer.try_send(&ops)
+    }
+
+    fn try_receive(&mut self, buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        self.layer.try_receive(buf)
+    }
+
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        let ops = self.decompose(ops)?;
+        self.layer.try_send_receive(&ops, buf)
+    }
+}
+
+macro_rules! impl_gates {
+    ($($gate:ident),*) => {
+        $(
+            impl<L> $gate for DecomposeLayer<L>
+                where L: Layer<Operation=OpArgs<L>> + HGate + TGate + CXGate,
+                      L::Qubit: Clone,
+                      L::Slot: Clone {}
+        )*
+    }
+}
+
+impl_gates!(PauliGate, HGate, SGate, TGate, CXGate, CZGate, SwapGate, CCXGate, CYGate, ISwapGate);
+
+/// Rotations are available for any inner layer, but they are lowered only when RZ is native
+/// or the angle is a multiple of π/4. Other angles make `try_send` return `Error::InvalidOperation`
+/// and make `send` panic, before anything is sent.
+impl<L> RotationGate for DecomposeLayer<L>
+    where L: Layer<Operation=OpArgs<L>> + HGate + TGate + CXGate,
+          L::Qubit: Clone,
+          L::Slot: Clone {}
+
+/// U3 is lowered into rotations, so it has the same limitation of angles as `RotationGate`.
+impl<L> U3Gate for DecomposeLayer<L>
+    where L: Layer<Operation=OpArgs<L>> + HGate + TGate + CXGate,
+          L::Qubit: Clone,
+          L::Slot: Clone {}
+
+impl<L> ConditionalGate for DecomposeLayer<L>
+    where L: Layer<Operation=OpArgs<L>> + HGate + TGate + CXGate + ConditionalGate,
+          L::Qubit: Clone,
+          L::Slot: Clone {}
+
+impl<L> ResetGate for DecomposeLayer<L>
+    where L: Layer<Operation=OpArgs<L>> + HGate + TGate + CXGate + ResetGate,
+          L::Qubit: Clone,
+          L::Slot: Clone {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use num_complex::Complex64;
+    use crate::{OpsVec, sim::StateVectorLayer};
+
+    fn build<L>(ops: &mut OpsVec<L>)
+        where L: Layer<Operation=OpArgs<L>, Qubit=u32> + PauliGate + HGate + SGate + TGate + CXGate +
+                 RotationGate + U3Gate + CZGate + SwapGate + CCXGate + CYGate + ISwapGate
+    {
+        ops.initialize();
+        ops.h(0);
+        ops.y(1);
+        ops.rx(2, PI / 2.0);
+        ops.ccx(0, 1, 2);
+        ops.cz(2, 0);
+        ops.swap(0, 1);
+        ops.iswap(1, 2);
+        ops.cy(2, 0);
+        ops.u3(1, PI / 4.0, -PI / 2.0, 3.0 * PI / 4.0);
+        ops.sdg(0);
+    }
+
+    fn fidelity(a: &[Complex64], b: &[Complex64]) -> f64 {
+        a.iter().zip(b).map(|(x, y)| x.conj() * y).sum::<Complex64>().norm()
+    }
+
+    #[test]
+    fn decompose() {
+        let mut expected = StateVectorLayer::new(3);
+        let mut ops = expected.opsvec();
+        build(&mut ops);
+        expected.send(ops.as_ref());
+
+        let mut layer = DecomposeLayer::new(StateVectorLayer::new(3));
+        let mut ops = layer.opsvec();
+        build(&mut ops);
+        let lowered = layer.decompose(ops.as_ref()).unwrap();
+        assert!(lowered.iter().all(|op| BASIS.contains(&op.id())));
+        layer.send(ops.as_ref());
+        assert!((fidelity(expected.state(), layer.inner().state()) - 1.0).abs() < 1e-9);
+
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.rz(0, 0.3);
+        assert!(matches!(layer.try_send(ops.as_ref()), Err(Error::InvalidOperation(_))));
+        // Nothing is sent when an operation can't be lowered.
+        assert!((fidelity(expected.state(), layer.inner().state()) - 1.0).abs() < 1e-9);
+        layer.set_native(vec![opid::RZ, opid::X]);
+        let lowered = layer.decompose(ops.as_ref()).unwrap();
+        assert!(matches!(lowered[..], [OpArgs::Empty(opid::INIT), OpArgs::QD(opid::RZ, 0, _)]));
+
+        let mut ops = layer.opsvec();
+        ops.reset(1);
+        assert!(matches!(layer.decompose(ops.as_ref()).unwrap()[..], [OpArgs::Q(opid::RESET, 1)]));
+    }
+}
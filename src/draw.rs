@@ -0,0 +1,355 @@
+//! Text circuit diagrams.
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use crate::{Layer, operations::{opid, OpArgs}};
+
+/// Characters used by `Diagram`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+struct Symbols {
+    wire: char,
+    link: char,
+    cross: char,
+    control: &'static str,
+    target: &'static str,
+    swap: &'static str,
+    dagger: &'static str,
+}
+
+const UNICODE: Symbols = Symbols {
+    wire: '─', link: '│', cross: '┼', control: "●", target: "⊕", swap: "×", dagger: "†",
+};
+
+const ASCII: Symbols = Symbols {
+    wire: '-', link: '|', cross: '+', control: "*", target: "(+)", swap: "x", dagger: "dg",
+};
+
+impl Charset {
+    fn symbols(self) -> &'static Symbols {
+        match self {
+            Charset::Unicode => &UNICODE,
+            Charset::Ascii => &ASCII,
+        }
+    }
+}
+
+/// Formats an angle with at most 3 decimal places.
+fn angle(x: f64) -> String {
+    let s = format!("{:.3}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+fn qubits<L: Layer + ?Sized>(op: &OpArgs<L>) -> Vec<&L::Qubit> {
+    match op {
+        OpArgs::Q(_, q) | OpArgs::QS(_, q, _) | OpArgs::QF(_, q, _) | OpArgs::QD(_, q, _) |
+        OpArgs::QFF(_, q, _, _) | OpArgs::QDDD(_, q, _, _, _) => vec![q],
+        OpArgs::QQ(_, a, b) => vec![a, b],
+        OpArgs::QQQ(_, a, b, c) => vec![a, b, c],
+        OpArgs::Empty(_) | OpArgs::Var(_, _) => vec![],
+        OpArgs::If(_, _, _, op) => qubits(op),
+    }
+}
+
+/// Gets the slots in the conditions and the slot written by the operation.
+fn slots<L: Layer + ?Sized>(op: &OpArgs<L>) -> (Vec<&L::Slot>, Option<&L::Slot>) {
+    match op {
+        OpArgs::QS(_, _, s) => (vec![], Some(s)),
+        OpArgs::If(_, slots, _, op) => {
+            let (mut read, written) = self::slots(op);
+            read.extend(slots);
+            (read, written)
+        },
+        _ => (vec![], None),
+    }
+}
+
+enum Piece {
+    Label(String),
+    Cross,
+}
+
+struct Column {
+    width: usize,
+    pieces: Vec<Option<Piece>>,
+    links: Vec<bool>,
+}
+
+/// Wrapper which draws operations as a text circuit diagram by `Display` and `Debug`.
+///
+/// Each qubit used by the operations has a wire, and gates are packed into columns
+/// from left to right. Gates on more than one qubit are connected by vertical lines.
+/// Initialization is drawn as `|0>` on every wire, reset as `|0>` on its wire,
+/// and measurement as `M[slot]`.
+/// Conditional operations have the condition after their labels, e.g. `X?c[0]=1`,
+/// and they are drawn after the measurements into their slots.
+/// Operations without builtin symbols are drawn by their IDs, e.g. `#256`.
+pub struct Diagram<'a, L: Layer + ?Sized> {
+    ops: &'a [OpArgs<L>],
+    charset: Charset,
+}
+
+impl<'a, L: Layer + ?Sized> Diagram<'a, L> {
+    /// Makes a diagram drawn with Unicode box-drawing characters.
+    pub fn new(ops: &'a [OpArgs<L>]) -> Self {
+        Diagram { ops, charset: Charset::Unicode }
+    }
+
+    /// Sets the characters used for drawing.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+}
+
+impl<'a, L> Diagram<'a, L>
+    where L: Layer + ?Sized,
+          L::Qubit: Ord + Display,
+          L::Slot: Display,
+{
+    /// Returns the labels of the operation with their wires, and whether they are connected.
+    fn pieces(&self, op: &OpArgs<L>, wires: &[&L::Qubit]) -> (Vec<(usize, String)>, bool) {
+        let sym = self.charset.symbols();
+        let wire = |q: &L::Qubit| wires.binary_search(&q).unwrap();
+        let all = |label: &str| (0..wires.len()).map(|w| (w, label.to_string())).collect();
+        match op {
+            OpArgs::Empty(opid::INIT) => (all("|0>"), false),
+            OpArgs::Empty(id) | OpArgs::Var(id, _) => (all(&format!("#{}", id)), false),
+            OpArgs::Q(id, q) => {
+                let label = match *id {
+                    opid::X => "X".to_string(),
+                    opid::Y => "Y".to_string(),
+                    opid::Z => "Z".to_string(),
+                    opid::H => "H".to_string(),
+                    opid::S => "S".to_string(),
+                    opid::SDG => format!("S{}", sym.dagger),
+                    opid::T => "T".to_string(),
+                    opid::TDG => format!("T{}", sym.dagger),
+                    opid::RESET => "|0>".to_string(),
+                    id => format!("#{}", id),
+                };
+                (vec![(wire(q), label)], false)
+            },
+            OpArgs::QS(opid::MEAS, q, s) => (vec![(wire(q), format!("M[{}]", s))], false),
+            OpArgs::QS(id, q, s) => (vec![(wire(q), format!("#{}[{}]", id, s))], false),
+            OpArgs::QF(id, q, x) => (vec![(wire(q), format!("#{}({})", id, angle(*x as f64)))], false),
+            OpArgs::QFF(id, q, x, y) =>
+                (vec![(wire(q), format!("#{}({},{})", id, angle(*x as f64), angle(*y as f64)))], false),
+            OpArgs::QD(id, q, theta) => {
+                let name = match *id {
+                    opid::RX => "RX".to_string(),
+                    opid::RY => "RY".to_string(),
+                    opid::RZ => "RZ".to_string(),
+                    id => format!("#{}", id),
+                };
+                (vec![(wire(q), format!("{}({})", name, angle(*theta)))], false)
+            },
+            OpArgs::QDDD(id, q, theta, phi, lambda) => {
+                let name = if *id == opid::U3 { "U3".to_string() } else { format!("#{}", id) };
+                let label = format!("{}({},{},{})", name, angle(*theta), angle(*phi), angle(*lambda));
+                (vec![(wire(q), label)], false)
+            },
+            OpArgs::QQ(id, a, b) => {
+                let (la, lb) = match *id {
+                    opid::CX => (sym.control.to_string(), sym.target.to_string()),
+                    opid::CZ => (sym.control.to_string(), sym.control.to_string()),
+                    opid::CY => (sym.control.to_string(), "Y".to_string()),
+                    opid::SWAP => (sym.swap.to_string(), sym.swap.to_string()),
+                    opid::ISWAP => ("iSWAP".to_string(), "iSWAP".to_string()),
+                    id => (format!("#{}", id), format!("#{}", id)),
+                };
+                (vec![(wire(a), la), (wire(b), lb)], true)
+            },
+            OpArgs::QQQ(id, a, b, c) => {
+                let (lc, lt) = match *id {
+                    opid::CCX => (sym.control.to_string(), sym.target.to_string()),
+                    id => (format!("#{}", id), format!("#{}", id)),
+                };
+                (vec![(wire(a), lc.clone()), (wire(b), lc), (wire(c), lt)], true)
+            },
+            OpArgs::If(_, slots, value, op) => {
+                let slots: Vec<_> = slots.iter().map(|s| s.to_string()).collect();
+                let cond = format!("?c[{}]={}", slots.join(","), value);
+                let (pieces, linked) = self.pieces(op, wires);
+                (pieces.into_iter().map(|(w, label)| (w, label + &cond)).collect(), linked)
+            },
+        }
+    }
+
+    fn columns(&self, wires: &[&L::Qubit]) -> Vec<Column> {
+        let n = wires.len();
+        let mut columns: Vec<Column> = vec![];
+        let mut free = vec![0; n];
+        // Columns after the last measurement into and the last condition on each slot.
+        // Slots are identified by their labels, which are all that the diagram shows.
+        let mut written: HashMap<String, usize> = HashMap::new();
+        let mut touched: HashMap<String, usize> = HashMap::new();
+        for op in self.ops {
+            let (read, write) = slots(op);
+            let read: Vec<String> = read.iter().map(|s| s.to_string()).collect();
+            let write = write.map(|s| s.to_string());
+            let (pieces, linked) = self.pieces(op, wires);
+            let lo = match pieces.iter().map(|(w, _)| *w).min() {
+                Some(lo) => lo,
+                None => continue,
+            };
+            let hi = pieces.iter().map(|(w, _)| *w).max().unwrap();
+            let after_slots = read.iter().filter_map(|s| written.get(s))
+                .chain(write.iter().filter_map(|s| touched.get(s)));
+            let col = free[lo..=hi].iter().chain(after_slots).copied().max().unwrap();
+            if col == columns.len() {
+                columns.push(Column { width: 1, pieces: (0..n).map(|_| None).collect(), links: vec![false; n] });
+            }
+            let column = &mut columns[col];
+            for (w, label) in pieces {
+                column.width = column.width.max(label.chars().count());
+                column.pieces[w] = Some(Piece::Label(label));
+            }
+            if linked {
+                for w in lo..=hi {
+                    if column.pieces[w].is_none() {
+                        column.pieces[w] = Some(Piece::Cross);
+                    }
+                }
+                for link in &mut column.links[lo..hi] {
+                    *link = true;
+                }
+            }
+            for f in &mut free[lo..=hi] {
+                *f = col + 1;
+            }
+            for s in read {
+                let t = touched.entry(s).or_insert(0);
+                *t = (*t).max(col + 1);
+            }
+            if let Some(s) = write {
+                written.insert(s.clone(), col + 1);
+                touched.insert(s, col + 1);
+            }
+        }
+        columns
+    }
+}
+
+impl<'a, L> Display for Diagram<'a, L>
+    where L: Layer + ?Sized,
+          L::Qubit: Ord + Display,
+          L::Slot: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sym = self.charset.symbols();
+        let mut wires: Vec<&L::Qubit> = self.ops.iter().flat_map(qubits).collect();
+        wires.sort();
+        wires.dedup();
+        if wires.is_empty() {
+            return Ok(());
+        }
+        let names: Vec<String> = wires.iter().map(|q| q.to_string()).collect();
+        let name_width = names.iter().map(|s| s.chars().count()).max().unwrap();
+
+        // Wires are on even rows, and vertical lines between them are on odd rows.
+        let mut rows = vec![String::new(); 2 * wires.len() - 1];
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i % 2 == 0 {
+                row.push_str(&format!("{:>w$}: ", names[i / 2], w = name_width));
+                row.push(sym.wire);
+            } else {
+                row.push_str(&" ".repeat(name_width + 3));
+            }
+        }
+        let repeat = |c: char, n: usize| c.to_string().repeat(n);
+        for column in self.columns(&wires) {
+            // Odd width puts vertical lines at the center.
+            let width = column.width | 1;
+            for (i, row) in rows.iter_mut().enumerate() {
+                if i % 2 == 0 {
+                    match &column.pieces[i / 2] {
+                        None => row.push_str(&repeat(sym.wire, width)),
+                        Some(Piece::Label(label)) => {
+                            let left = (width - label.chars().count()) / 2;
+                            let right = width - label.chars().count() - left;
+                            row.push_str(&repeat(sym.wire, left));
+                            row.push_str(label);
+                            row.push_str(&repeat(sym.wire, right));
+                        },
+                        Some(Piece::Cross) => {
+                            row.push_str(&repeat(sym.wire, width / 2));
+                            row.push(sym.cross);
+                            row.push_str(&repeat(sym.wire, width / 2));
+                        },
+                    }
+                    row.push(sym.wire);
+                } else if column.links[i / 2] {
+                    row.push_str(&repeat(' ', width / 2));
+                    row.push(sym.link);
+                    row.push_str(&repeat(' ', width / 2 + 1));
+                } else {
+                    row.push_str(&repeat(' ', width + 1));
+                }
+            }
+        }
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            f.write_str(row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, L> fmt::Debug for Diagram<'a, L>
+    where L: Layer + ?Sized,
+          L::Qubit: Ord + Display,
+          L::Slot: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// Draws operations as a text circuit diagram with Unicode box-drawing characters.
+pub fn draw<L>(ops: &[OpArgs<L>]) -> String
+    where L: Layer + ?Sized,
+          L::Qubit: Ord + Display,
+          L::Slot: Display,
+{
+    Diagram::new(ops).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::StateVectorLayer;
+
+    #[test]
+    fn diagram() {
+        let mut ops = StateVectorLayer::new(3).opsvec();
+        ops.h(0);
+        ops.cx(0, 2);
+        ops.tdg(1);
+        ops.measure(2, 0);
+        ops.rz(0, -1.5);
+        assert_eq!(draw(ops.as_ref()), "0: ─H─●─RZ(-1.5)──\n      │\n1: ───┼────T†─────\n      │\n2: ───⊕───M[0]────");
+        assert_eq!(format!("{:?}", Diagram::new(ops.as_ref()).charset(Charset::Ascii)),
+                   "0: -H--*--RZ(-1.5)--\n       |\n1: ----+-----Tdg----\n       |\n2: ---(+)---M[0]----");
+
+        let mut ops = StateVectorLayer::new(2).opsvec();
+        ops.initialize();
+        ops.swap(1, 0);
+        assert_eq!(draw(ops.as_ref()), "0: ─|0>─×─\n        │\n1: ─|0>─×─");
+        assert_eq!(draw::<StateVectorLayer>(&[]), "");
+
+        let mut ops = StateVectorLayer::new(3).opsvec();
+        ops.h(1);
+        ops.measure(1, 0);
+        ops.if_slot(0, true, |o| o.x(0));
+        ops.measure(2, 0);
+        assert_eq!(draw(ops.as_ref()), "0: ─────────X?c[0]=1────────\n\n1: ─H─M[0]──────────────────\n\n2: ───────────────────M[0]──");
+    }
+}
@@ -0,0 +1,52 @@
+//! Error type for fallible operations.
+use std::fmt;
+use std::time::Duration;
+
+/// Errors reported by layers.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The operation ID is not supported.
+    UnsupportedOperation(u16),
+    /// Arguments of the operation are invalid.
+    InvalidOperation(String),
+    /// The qubit does not exist.
+    QubitOutOfRange(String),
+    /// The slot does not exist.
+    SlotOutOfRange(String),
+    /// The parameter is not bound.
+    UnboundParameter(String),
+    /// The range of slots is invalid.
+    InvalidRange { start: usize, stop: usize },
+    /// The backend failed.
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+    /// The request is timed out.
+    Timeout(Duration),
+    /// The request is cancelled.
+    Cancelled,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnsupportedOperation(id) => write!(f, "Unsupported operation (ID: {}).", id),
+            Error::InvalidOperation(msg) => write!(f, "Invalid operation: {}.", msg),
+            Error::QubitOutOfRange(q) => write!(f, "Qubit {} is out of range.", q),
+            Error::SlotOutOfRange(s) => write!(f, "Slot {} is out of range.", s),
+            Error::UnboundParameter(name) => write!(f, "Parameter `{}` is not bound.", name),
+            Error::InvalidRange { start, stop } => write!(f, "Invalid range {}..{}.", start, stop),
+            Error::Backend(e) => write!(f, "Backend error: {}", e),
+            Error::Timeout(d) => write!(f, "Timed out after {:?}.", d),
+            Error::Cancelled => write!(f, "Cancelled."),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Backend(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! Layer which tracks Pauli gates in a classical Pauli frame.
+use std::collections::{HashMap, HashSet};
+
+use crate::{Error, Layer, TryLayer, Measured,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs},
+            sim};
+
+/// Returns the qubits of the operation, or None if the operation may act on all qubits.
+fn qubits<L>(op: &OpArgs<L>) -> Option<Vec<u32>>
+    where L: Layer<Qubit=u32> + ?Sized
+{
+    match *op {
+        OpArgs::Q(_, q) | OpArgs::QS(_, q, _) | OpArgs::QF(_, q, _) | OpArgs::QD(_, q, _) |
+        OpArgs::QFF(_, q, _, _) | OpArgs::QDDD(_, q, _, _, _) => Some(vec![q]),
+        OpArgs::QQ(_, a, b) => Some(vec![a, b]),
+        OpArgs::QQQ(_, a, b, c) => Some(vec![a, b, c]),
+        OpArgs::If(_, _, _, ref op) => qubits(op),
+        OpArgs::Empty(_) | OpArgs::Var(_, _) => None,
+    }
+}
+
+/// Pauli frame and the measured results flipped by it.
+#[derive(Debug, Clone, Default)]
+struct Frame {
+    /// X and Z components of the Pauli on each qubit.
+    paulis: HashMap<u32, (bool, bool)>,
+    flips: HashSet<u32>,
+}
+
+impl Frame {
+    fn get(&self, q: u32) -> (bool, bool) {
+        self.paulis.get(&q).copied().unwrap_or((false, false))
+    }
+
+    fn set(&mut self, q: u32, p: (bool, bool)) {
+        if p == (false, false) {
+            self.paulis.remove(&q);
+        } else {
+            self.paulis.insert(q, p);
+        }
+    }
+
+    /// Moves the frame through a builtin Clifford gate.
+    fn conjugate<L>(&mut self, op: &OpArgs<L>)
+        where L: Layer<Qubit=u32, Slot=u32> + ?Sized
+    {
+        match *op {
+            OpArgs::Q(opid::H, q) => {
+                let (x, z) = self.get(q);
+                self.set(q, (z, x));
+            },
+            OpArgs::Q(opid::S | opid::SDG, q) => {
+                let (x, z) = self.get(q);
+                self.set(q, (x, z ^ x));
+            },
+            OpArgs::QQ(opid::CX, c, t) => {
+                let ((xc, zc), (xt, zt)) = (self.get(c), self.get(t));
+                self.set(c, (xc, zc ^ zt));
+                self.set(t, (xt ^ xc, zt));
+            },
+            OpArgs::QQ(opid::CZ | opid::CY | opid::SWAP | opid::ISWAP, _, _) => {
+                for op in sim::decompose(op) {
+                    self.conjugate(&op);
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Applies the Paulis on qubit `q` physically and removes them from the frame.
+    fn flush<L>(&mut self, q: u32, out: &mut Vec<OpArgs<L>>)
+        where L: Layer<Qubit=u32> + ?Sized
+    {
+        let (x, z) = self.get(q);
+        if x {
+            out.push(OpArgs::Q(opid::X, q));
+        }
+        if z {
+            out.push(OpArgs::Q(opid::Z, q));
+        }
+        self.set(q, (false, false));
+    }
+
+    fn flush_op<L>(&mut self, op: &OpArgs<L>, out: &mut Vec<OpArgs<L>>)
+        where L: Layer<Qubit=u32> + ?Sized
+    {
+        let qs = match qubits(op) {
+            Some(qs) => qs,
+            None => self.paulis.keys().copied().collect(),
+        };
+        for q in qs {
+            self.flush(q, out);
+        }
+    }
+
+    /// Updates the frame by the operation and writes the operations which should be sent.
+    fn track<L>(&mut self, op: OpArgs<L>, out: &mut Vec<OpArgs<L>>) -> Result<(), Error>
+        where L: Layer<Qubit=u32, Slot=u32> + ?Sized
+    {
+        use OpArgs::{Q, QD, QQ};
+        match op {
+            OpArgs::Empty(opid::INIT) => {
+                self.paulis.clear();
+                self.flips.clear();
+                out.push(op);
+            },
+            Q(opid::X | opid::Y | opid::Z, q) => {
+                let (x, z) = self.get(q);
+                let id = op.id();
+                self.set(q, (x ^ (id != opid::Z), z ^ (id != opid::X)));
+            },
+            Q(opid::H | opid::S | opid::SDG, _) | QQ(opid::CX | opid::CZ | opid::CY | opid::SWAP | opid::ISWAP, _, _) => {
+                self.conjugate(&op);
+                out.push(op);
+            },
+            // T X = X T† up to global phase, and T commutes with Z.
+            Q(id @ (opid::T | opid::TDG), q) => {
+                let id = match (self.get(q).0, id) {
+                    (true, opid::T) => opid::TDG,
+                    (true, _) => opid::T,
+                    (false, id) => id,
+                };
+                out.push(Q(id, q));
+            },
+            QD(id @ (opid::RX | opid::RY | opid::RZ), q, theta) => {
+                let (x, z) = self.get(q);
+                let anticommutes = match id {
+                    opid::RX => z,
+                    opid::RY => x != z,
+                    _ => x,
+                };
+                out.push(QD(id, q, if anticommutes { -theta } else { theta }));
+            },
+            OpArgs::QS(opid::MEAS, q, s) => {
+                let (x, _) = self.get(q);
+                if x {
+                    self.flips.insert(s);
+                } else {
+                    self.flips.remove(&s);
+                }
+                // Z on a measured qubit is a global phase.
+                self.set(q, (x, false));
+                out.push(op);
+            },
+            Q(opid::RESET, q) => {
+                self.set(q, (false, false));
+                out.push(op);
+            },
+            // Whether the operation is applied is unknown until the condition is read,
+            // so the frame is flushed and the expected value is given in physical results.
+            // A conditional measurement leaves its slot flipped only if it is not taken,
+            // so it is rejected unless the slot has no flip.
+            OpArgs::If(id, slots, mut value, inner) => {
+                if measured_slot(&inner).is_some_and(|s| self.flips.contains(&s)) {
+                    return Err(Error::UnsupportedOperation(opid::IF));
+                }
+                self.flush_op(&inner, out);
+                for (i, s) in slots.iter().enumerate() {
+                    if i < 64 && self.flips.contains(s) {
+                        value ^= 1 << i;
+                    }
+                }
+                out.push(OpArgs::If(id, slots, value, inner));
+            },
+            op => {
+                self.flush_op(&op, out);
+                out.push(op);
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Returns the slot written by the measurement, which may be under conditions.
+fn measured_slot<L>(op: &OpArgs<L>) -> Option<u32>
+    where L: Layer<Slot=u32> + ?Sized
+{
+    match *op {
+        OpArgs::QS(opid::MEAS, _, s) => Some(s),
+        OpArgs::If(_, _, _, ref op) => measured_slot(op),
+        _ => None,
+    }
+}
+
+/// Layer which keeps Pauli gates in a classical Pauli frame instead of sending them.
+///
+/// The frame is moved through H, S, S†, CX, CZ, CY, SWAP and iSWAP gates. T gates and
+/// X, Y and Z rotations are sent with their frame-dependent corrections, e.g. T under X is sent as T†.
+/// Pauli gates are applied physically before other operations on the qubits, such as U3 and Toffoli.
+/// Measured results flipped by the frame are corrected when they are read from the buffer.
+/// A conditional measurement into a slot flipped by the frame is unsupported, because the flip
+/// depends on whether the measurement is taken.
+///
+/// The frame is cleared by initialize, so every sent circuit should start with initialize.
+#[derive(Debug)]
+pub struct PauliFrameLayer<L> {
+    layer: L,
+    frame: Frame,
+}
+
+impl<L> PauliFrameLayer<L>
+    where L: Layer<Operation=OpArgs<L>, Qubit=u32, Slot=u32> + PauliGate
+{
+    pub fn new(layer: L) -> Self {
+        PauliFrameLayer { layer, frame: Frame::default() }
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.layer
+    }
+
+    pub fn into_inner(self) -> L {
+        self.layer
+    }
+
+    /// Gets the Pauli on qubit `q` as `(x, z)`, which means X^x Z^z.
+    pub fn pauli(&self, q: u32) -> (bool, bool) {
+        self.frame.get(q)
+    }
+
+    /// Sends the Paulis in the frame and clears the frame. Corrections of measured results are kept.
+    pub fn flush(&mut self) -> L::Requested {
+        let mut ops = vec![];
+        let mut qubits: Vec<_> = self.frame.paulis.keys().copied().collect();
+        qubits.sort_unstable();
+        for q in qubits {
+            self.frame.flush(q, &mut ops);
+        }
+        self.layer.send(&ops)
+    }
+
+    /// Returns operations for the inner layer and the frame after them.
+    fn track(&self, ops: &[OpArgs<Self>]) -> Result<(Vec<OpArgs<L>>, Frame), Error> {
+        let mut frame = self.frame.clone();
+        let mut tracked = Vec::with_capacity(ops.len());
+        for op in ops {
+            frame.track(op.try_clone().ok_or(Error::UnsupportedOperation(op.id()))?.cast(), &mut tracked)?;
+        }
+        Ok((tracked, frame))
+    }
+}
+
+impl<L> Layer for PauliFrameLayer<L>
+    where L: Layer<Operation=OpArgs<L>, Qubit=u32, Slot=u32> + PauliGate
+{
+    type Operation = OpArgs<Self>;
+    type Qubit = u32;
+    type Slot = u32;
+    type Buffer = PauliFrameBuffer<L>;
+    type Requested = L::Requested;
+    type Response = L::Response;
+
+    fn make_buffer(&self) -> Self::Buffer {
+        PauliFrameBuffer { buf: self.layer.make_buffer(), flips: HashSet::new() }
+    }
+
+    fn send(&mut self, ops: &[Self::Operation]) -> L::Requested {
+        let (ops, frame) = self.track(ops).unwrap_or_else(|e| panic!("{}", e));
+        self.frame = frame;
+        self.layer.send(&ops)
+    }
+
+    fn receive(&mut self, buf: &mut Self::Buffer) -> L::Response {
+        let response = self.layer.receive(&mut buf.buf);
+        buf.flips.clone_from(&self.frame.flips);
+        response
+    }
+
+    fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut Self::Buffer) -> L::Response {
+        let (ops, frame) = self.track(ops).unwrap_or_else(|e| panic!("{}", e));
+        self.frame = frame;
+        let response = self.layer.send_receive(&ops, &mut buf.buf);
+        buf.flips.clone_from(&self.frame.flips);
+        response
+    }
+}
+
+impl<L> TryLayer for PauliFrameLayer<L>
+    where L: TryLayer<Operation=OpArgs<L>, Qubit=u32, Slot=u32> + PauliGate
+{
+    /// Sends operations. The frame is not changed when an error is returned.
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<L::Requested, Error> {
+        let (ops, frame) = self.track(ops)?;
+        let requested = self.layer.try_send(&ops)?;
+        self.frame = frame;
+        Ok(requested)
+    }
+
+    fn try_receive(&mut self, buf: &mut Self::Buffer) -> Result<L::Response, Error> {
+        let response = self.layer.try_receive(&mut buf.buf)?;
+        buf.flips.clone_from(&self.frame.flips);
+        Ok(response)
+    }
+
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut Self::Buffer) -> Result<L::Response, Error> {
+        let (ops, frame) = self.track(ops)?;
+        let response = self.layer.try_send_receive(&ops, &mut buf.buf)?;
+        self.frame = frame;
+        buf.flips.clone_from(&self.frame.flips);
+        Ok(response)
+    }
+}
+
+macro_rules! impl_gates {
+    ($($gate:ident),*) => {
+        $(
+            impl<L> $gate for PauliFrameLayer<L>
+                where L: Layer<Operation=OpArgs<L>, Qubit=u32, Slot=u32> + PauliGate + $gate {}
+        )*
+    }
+}
+
+impl_gates!(HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+            CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate);
+
+impl<L> PauliGate for PauliFrameLayer<L>
+    where L: Layer<Operation=OpArgs<L>, Qubit=u32, Slot=u32> + PauliGate {}
+
+/// Buffer of `PauliFrameLayer`, which corrects measured results by the frame.
+#[derive(Debug)]
+pub struct PauliFrameBuffer<L: Layer> {
+    buf: L::Buffer,
+    flips: HashSet<u32>,
+}
+
+impl<L: Layer> PauliFrameBuffer<L> {
+    /// Gets the buffer of the inner layer, which has uncorrected results.
+    pub fn inner(&self) -> &L::Buffer {
+        &self.buf
+    }
+}
+
+impl<L: Layer<Slot=u32>> Measured for PauliFrameBuffer<L> {
+    type Slot = u32;
+
+    fn get(&self, n: u32) -> bool {
+        self.buf.get(n) ^ self.flips.contains(&n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex64;
+    use crate::sim::StateVectorLayer;
+
+    #[test]
+    fn frame() {
+        let mut layer = PauliFrameLayer::new(StateVectorLayer::new(3));
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.x(0);
+        ops.cx(0, 1);
+        ops.h(2);
+        ops.z(2);
+        ops.h(2);
+        for q in 0..3 {
+            ops.measure(q, q);
+        }
+        let mut buf = layer.make_buffer();
+        layer.send_receive(ops.as_ref(), &mut buf);
+        assert_eq!(buf.get_range_u8(0, 3), 0b111);
+        assert_eq!(buf.inner().get_range_u8(0, 3), 0);
+        assert_eq!(layer.pauli(1), (true, false));
+
+        // X T X T is the identity, which needs T under X to be sent as T†.
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.x(0);
+        ops.t(0);
+        ops.x(0);
+        ops.t(0);
+        ops.h(0);
+        ops.x(1);
+        ops.measure(1, 1);
+        ops.if_slot(1, true, |ops| ops.x(2));
+        layer.send(ops.as_ref());
+        assert!((layer.inner().state()[0b100] - Complex64::new(1.0, 0.0)).norm() < 1e-9);
+        assert_eq!(layer.pauli(1), (true, false));
+        layer.flush();
+        assert_eq!(layer.pauli(1), (false, false));
+        assert!((layer.inner().state()[0b110] - Complex64::new(1.0, 0.0)).norm() < 1e-9);
+    }
+    #[test]
+    fn conditional_measurement() {
+        let mut layer = PauliFrameLayer::new(StateVectorLayer::new(2));
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.x(0);
+        ops.measure(0, 0);
+        ops.x(1);
+        ops.measure(1, 1);
+        ops.if_slot(0, true, |ops| ops.measure(1, 1));
+        assert!(matches!(layer.try_send(ops.as_ref()), Err(Error::UnsupportedOperation(opid::IF))));
+
+        // The slot has no flip, so the result is physical whether the measurement is taken or not.
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.x(0);
+        ops.measure(0, 0);
+        ops.x(1);
+        ops.if_slot(0, true, |ops| ops.measure(1, 1));
+        let mut buf = layer.make_buffer();
+        layer.send_receive(ops.as_ref(), &mut buf);
+        assert_eq!(buf.get_range_u8(0, 2), 0b11);
+        assert_eq!(layer.pauli(1), (false, false));
+    }
+}
@@ -14,3 +14,30 @@ pub trait TGate : Layer {}
 
 /// Layers for which implements CNOT gates.
 pub trait CXGate : Layer {}
+
+/// Layers for which implements X, Y and Z rotation gates.
+pub trait RotationGate : Layer {}
+
+/// Layers for which implements generic single qubit gate U3.
+pub trait U3Gate : Layer {}
+
+/// Layers for which implements CZ gates.
+pub trait CZGate : Layer {}
+
+/// Layers for which implements SWAP gates.
+pub trait SwapGate : Layer {}
+
+/// Layers for which implements Toffoli (CCX) gates.
+pub trait CCXGate : Layer {}
+
+/// Layers for which implements CY gates.
+pub trait CYGate : Layer {}
+
+/// Layers for which implements iSWAP gates.
+pub trait ISwapGate : Layer {}
+
+/// Layers for which implements operations conditioned on measured slots.
+pub trait ConditionalGate : Layer {}
+
+/// Layers for which implements reset of a qubit.
+pub trait ResetGate : Layer {}
@@ -1,7 +1,12 @@
 use std::marker::PhantomData;
 use crate::{Layer,
-            gates::{PauliGate, HGate, SGate, TGate, CXGate},
-            operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation}};
+            wrapped::{TransparentWrapper, transparent_wrapper},
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation,
+                         RotationOperation, U3Operation,
+                         CZOperation, SwapOperation, CCXOperation, CYOperation, ISwapOperation,
+                         ConditionalOperation, ResetOperation}};
 
 #[derive(Debug)]
 pub struct InjectLayer<L: Layer,
@@ -42,7 +47,7 @@ impl<L: Layer,
     }
 
     fn send(&mut self, ops: &[Self::Operation]) -> L::Requested {
-        (self.f_send)(&mut self.layer, unsafe { std::mem::transmute(ops) })
+        (self.f_send)(&mut self.layer, TransparentWrapper::peel_slice(ops))
     }
 
     fn receive(&mut self, buf: &mut L::Buffer) -> L::Response {
@@ -50,7 +55,7 @@ impl<L: Layer,
     }
 
     fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> L::Response {
-        (self.f_send_receive)(&mut self.layer, unsafe { std::mem::transmute(ops) }, buf)
+        (self.f_send_receive)(&mut self.layer, TransparentWrapper::peel_slice(ops), buf)
     }
 }
 
@@ -74,26 +79,54 @@ impl<L: Layer + CXGate,
      F: Fn(&mut L, &[L::Operation]) -> L::Requested,
      G: Fn(&mut L, &mut L::Buffer) -> L::Response,
      H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> CXGate for InjectLayer<L, F, G, H> {}
-
-#[repr(transparent)]
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct InjectOperation<L: Layer,
-                            F: Fn(&mut L, &[L::Operation]) -> L::Requested,
-                            G: Fn(&mut L, &mut L::Buffer) -> L::Response,
-                            H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
-(L::Operation, PhantomData<(F, G, H)>);
-
-impl<L: Layer,
+impl<L: Layer + RotationGate,
      F: Fn(&mut L, &[L::Operation]) -> L::Requested,
      G: Fn(&mut L, &mut L::Buffer) -> L::Response,
-     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> InjectOperation<L, F, G, H>
-{
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> RotationGate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + U3Gate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> U3Gate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + CZGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> CZGate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + SwapGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> SwapGate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + CCXGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> CCXGate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + CYGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> CYGate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + ISwapGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> ISwapGate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + ConditionalGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> ConditionalGate for InjectLayer<L, F, G, H> {}
+impl<L: Layer + ResetGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response> ResetGate for InjectLayer<L, F, G, H> {}
+
+transparent_wrapper! {
+    /// Operation of `InjectLayer`.
+    pub struct InjectOperation<L, F, G, H>;
+}
+
+impl<L: Layer, F, G, H> InjectOperation<L, F, G, H> {
     pub fn new(op: L::Operation) -> Self {
-        Self (op, PhantomData)
+        Self(op, PhantomData)
     }
 }
 
-
 impl<L: Layer,
      F: Fn(&mut L, &[L::Operation]) -> L::Requested,
      G: Fn(&mut L, &mut L::Buffer) -> L::Response,
@@ -185,3 +218,119 @@ CXOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
         Self::new(L::Operation::cx(c, t))
     }
 }
+
+impl<L: Layer + RotationGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+RotationOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + RotationOperation<L>
+{
+    fn rx(q: L::Qubit, theta: f64) -> Self {
+        Self::new(L::Operation::rx(q, theta))
+    }
+
+    fn ry(q: L::Qubit, theta: f64) -> Self {
+        Self::new(L::Operation::ry(q, theta))
+    }
+
+    fn rz(q: L::Qubit, theta: f64) -> Self {
+        Self::new(L::Operation::rz(q, theta))
+    }
+}
+
+impl<L: Layer + U3Gate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+U3Operation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + U3Operation<L>
+{
+    fn u3(q: L::Qubit, theta: f64, phi: f64, lambda: f64) -> Self {
+        Self::new(L::Operation::u3(q, theta, phi, lambda))
+    }
+}
+
+impl<L: Layer + CZGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+CZOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + CZOperation<L>
+{
+    fn cz(c: L::Qubit, t: L::Qubit) -> Self {
+        Self::new(L::Operation::cz(c, t))
+    }
+}
+
+impl<L: Layer + SwapGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+SwapOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + SwapOperation<L>
+{
+    fn swap(a: L::Qubit, b: L::Qubit) -> Self {
+        Self::new(L::Operation::swap(a, b))
+    }
+}
+
+impl<L: Layer + CCXGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+CCXOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + CCXOperation<L>
+{
+    fn ccx(c1: L::Qubit, c2: L::Qubit, t: L::Qubit) -> Self {
+        Self::new(L::Operation::ccx(c1, c2, t))
+    }
+}
+
+impl<L: Layer + CYGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+CYOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + CYOperation<L>
+{
+    fn cy(c: L::Qubit, t: L::Qubit) -> Self {
+        Self::new(L::Operation::cy(c, t))
+    }
+}
+
+impl<L: Layer + ISwapGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+ISwapOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + ISwapOperation<L>
+{
+    fn iswap(a: L::Qubit, b: L::Qubit) -> Self {
+        Self::new(L::Operation::iswap(a, b))
+    }
+}
+
+impl<L: Layer + ResetGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+ResetOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + ResetOperation<L>
+{
+    fn reset(q: L::Qubit) -> Self {
+        Self::new(L::Operation::reset(q))
+    }
+}
+
+impl<L: Layer + ConditionalGate,
+     F: Fn(&mut L, &[L::Operation]) -> L::Requested,
+     G: Fn(&mut L, &mut L::Buffer) -> L::Response,
+     H: Fn(&mut L, &[L::Operation], &mut L::Buffer) -> L::Response>
+ConditionalOperation<InjectLayer<L, F, G, H>> for InjectOperation<L, F, G, H>
+    where L::Operation: Operation<L> + ConditionalOperation<L>
+{
+    fn if_slots(slots: Vec<L::Slot>, value: u64, op: Self) -> Self {
+        Self::new(L::Operation::if_slots(slots, value, op.0))
+    }
+}
@@ -1,5 +1,6 @@
 //! Trait for abstract quantum computer.
-use crate::{Measured, OpsVec};
+use num_traits::cast::NumCast;
+use crate::{Counts, Error, Measured, OpsVec};
 
 /// Sends operations and receives result.
 pub trait Layer {
@@ -22,6 +23,19 @@ pub trait Layer {
         self.receive(buf)
     }
 
+    /// Sends and receives `shots` times, and counts measured slots `0..n_slots`.
+    fn run_shots(&mut self, ops: &[Self::Operation], shots: usize, n_slots: usize) -> Counts
+        where Self::Slot: NumCast
+    {
+        let mut counts = Counts::new(n_slots);
+        let mut buf = self.make_buffer();
+        for _ in 0..shots {
+            self.send_receive(ops, &mut buf);
+            counts.add_measured(&buf, 0);
+        }
+        counts
+    }
+
     /// Make new buffer for receiving result.
     fn make_buffer(&self) -> Self::Buffer;
 
@@ -30,3 +44,18 @@ pub trait Layer {
         OpsVec::new()
     }
 }
+
+/// Layer which reports failures as `Error` instead of panicking.
+pub trait TryLayer : Layer {
+    /// Sends operations.
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<Self::Requested, Error>;
+
+    /// Receives measured result.
+    fn try_receive(&mut self, buf: &mut Self::Buffer) -> Result<Self::Response, Error>;
+
+    /// Sends and receives.
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut Self::Buffer) -> Result<Self::Response, Error> {
+        self.try_send(ops)?;
+        self.try_receive(buf)
+    }
+}
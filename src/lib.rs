@@ -5,18 +5,41 @@
 
 pub mod gates;
 pub mod operations;
+pub mod wrapped;
 pub mod convert;
+pub mod mapping;
+pub mod routing;
+pub mod decompose;
+pub mod optimize;
+pub mod resource;
 pub mod inject;
+pub mod record;
+pub mod sim;
+pub mod qasm;
+pub mod draw;
+pub mod frame;
+pub mod surface;
+pub mod decoder;
 
-pub use gates::{PauliGate, HGate, SGate, TGate, CXGate};
-pub use operations::OpsVec;
+pub use gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate};
+pub use operations::{OpsVec, ParamOpsVec};
 
 mod layer;
-pub use layer::Layer;
+pub use layer::{Layer, TryLayer};
 
 mod measured;
 pub use measured::Measured;
 
+pub mod async_layer;
+pub use async_layer::AsyncLayer;
+
+mod error;
+pub use error::Error;
+
+mod counts;
+pub use counts::Counts;
+
 #[cfg(test)]
 mod tests {
     #[test]
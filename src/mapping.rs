@@ -0,0 +1,262 @@
+//! Layer which maps qubits and slots by runtime tables.
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::{Error, Layer, TryLayer, Measured,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::OpArgs};
+
+/// Layer which translates user qubits and slots to physical ones of the inner layer.
+///
+/// Unlike `QubitSlotConvertLayer`, the mapping is a table given at runtime,
+/// so it can be loaded from the device's qubit map and replaced later.
+/// Operations with unmapped qubits or slots are reported as errors by `TryLayer`,
+/// and `Layer::send` panics for them.
+#[derive(Debug)]
+pub struct MappingLayer<L: Layer, Q, S> {
+    layer: L,
+    qubits: HashMap<Q, L::Qubit>,
+    slots: Arc<HashMap<S, L::Slot>>,
+}
+
+impl<L, Q, S> MappingLayer<L, Q, S>
+    where L: Layer<Operation=OpArgs<L>>,
+          Q: Eq + Hash + Clone + Debug,
+          S: Eq + Hash + Clone + Debug,
+          L::Qubit: Clone,
+          L::Slot: Clone,
+{
+    /// Makes a layer with empty tables.
+    pub fn new(layer: L) -> Self {
+        MappingLayer { layer, qubits: HashMap::new(), slots: Arc::new(HashMap::new()) }
+    }
+
+    /// Makes a layer with given tables.
+    pub fn with_maps<IQ, IS>(layer: L, qubits: IQ, slots: IS) -> Self
+        where IQ: IntoIterator<Item=(Q, L::Qubit)>,
+              IS: IntoIterator<Item=(S, L::Slot)>,
+    {
+        MappingLayer { layer, qubits: qubits.into_iter().collect(), slots: Arc::new(slots.into_iter().collect()) }
+    }
+
+    /// Maps user qubit `q` to physical qubit `p`.
+    pub fn map_qubit(&mut self, q: Q, p: L::Qubit) -> &mut Self {
+        self.qubits.insert(q, p);
+        self
+    }
+
+    /// Maps user slot `s` to physical slot `p`.
+    ///
+    /// Buffers take the slot table when they receive results, so results received
+    /// before this call are read by the previous table.
+    pub fn map_slot(&mut self, s: S, p: L::Slot) -> &mut Self {
+        Arc::make_mut(&mut self.slots).insert(s, p);
+        self
+    }
+
+    /// Replaces the qubit table.
+    pub fn set_qubit_map<I: IntoIterator<Item=(Q, L::Qubit)>>(&mut self, qubits: I) {
+        self.qubits = qubits.into_iter().collect();
+    }
+
+    /// Replaces the slot table.
+    ///
+    /// Results received before this call are read by the previous table, as `map_slot`.
+    pub fn set_slot_map<I: IntoIterator<Item=(S, L::Slot)>>(&mut self, slots: I) {
+        self.slots = Arc::new(slots.into_iter().collect());
+    }
+
+    pub fn qubit_map(&self) -> &HashMap<Q, L::Qubit> {
+        &self.qubits
+    }
+
+    pub fn slot_map(&self) -> &HashMap<S, L::Slot> {
+        &self.slots
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.layer
+    }
+
+    pub fn into_inner(self) -> L {
+        self.layer
+    }
+
+    /// Translates operations into the operations of the inner layer.
+    pub fn translate(&self, ops: &[OpArgs<Self>]) -> Result<Vec<OpArgs<L>>, Error> {
+        ops.iter().map(|op| op.try_clone().ok_or(Error::UnsupportedOperation(op.id()))?.try_map(
+            |q| self.qubits.get(&q).cloned().ok_or_else(|| Error::QubitOutOfRange(format!("{:?}", q))),
+            |s| self.slots.get(&s).cloned().ok_or_else(|| Error::SlotOutOfRange(format!("{:?}", s))),
+        )).collect()
+    }
+}
+
+impl<L, Q, S> Layer for MappingLayer<L, Q, S>
+    where L: Layer<Operation=OpArgs<L>>,
+          Q: Eq + Hash + Clone + Debug,
+          S: Eq + Hash + Clone + Debug,
+          L::Qubit: Clone,
+          L::Slot: Clone,
+{
+    type Operation = OpArgs<Self>;
+    type Qubit = Q;
+    type Slot = S;
+    type Buffer = MappingBuffer<L, S>;
+    type Requested = L::Requested;
+    type Response = L::Response;
+
+    fn make_buffer(&self) -> Self::Buffer {
+        MappingBuffer { buf: self.layer.make_buffer(), slots: self.slots.clone() }
+    }
+
+    fn send(&mut self, ops: &[Self::Operation]) -> L::Requested {
+        let ops = self.translate(ops).unwrap_or_else(|e| panic!("{}", e));
+        self.layer.send(&ops)
+    }
+
+    fn receive(&mut self, buf: &mut Self::Buffer) -> L::Response {
+        buf.slots.clone_from(&self.slots);
+        self.layer.receive(&mut buf.buf)
+    }
+
+    fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut Self::Buffer) -> L::Response {
+        let ops = self.translate(ops).unwrap_or_else(|e| panic!("{}", e));
+        buf.slots.clone_from(&self.slots);
+        self.layer.send_receive(&ops, &mut buf.buf)
+    }
+}
+
+impl<L, Q, S> TryLayer for MappingLayer<L, Q, S>
+    where L: TryLayer<Operation=OpArgs<L>>,
+          Q: Eq + Hash + Clone + Debug,
+          S: Eq + Hash + Clone + Debug,
+          L::Qubit: Clone,
+          L::Slot: Clone,
+{
+    /// Sends operations. Nothing is sent when any qubit or slot is unmapped.
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<L::Requested, Error> {
+        let ops = self.translate(ops)?;
+        self.layer.try_send(&ops)
+    }
+
+    fn try_receive(&mut self, buf: &mut Self::Buffer) -> Result<L::Response, Error> {
+        buf.slots.clone_from(&self.slots);
+        self.layer.try_receive(&mut buf.buf)
+    }
+
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut Self::Buffer) -> Result<L::Response, Error> {
+        let ops = self.translate(ops)?;
+        buf.slots.clone_from(&self.slots);
+        self.layer.try_send_receive(&ops, &mut buf.buf)
+    }
+}
+
+impl<L: Layer<Operation=OpArgs<L>> + PauliGate, Q, S> PauliGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + HGate, Q, S> HGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + SGate, Q, S> SGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + TGate, Q, S> TGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + CXGate, Q, S> CXGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + RotationGate, Q, S> RotationGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + U3Gate, Q, S> U3Gate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + CZGate, Q, S> CZGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + SwapGate, Q, S> SwapGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + CCXGate, Q, S> CCXGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + CYGate, Q, S> CYGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + ISwapGate, Q, S> ISwapGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + ConditionalGate, Q, S> ConditionalGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>> + ResetGate, Q, S> ResetGate for MappingLayer<L, Q, S>
+    where Q: Eq + Hash + Clone + Debug, S: Eq + Hash + Clone + Debug, L::Qubit: Clone, L::Slot: Clone {}
+
+/// Buffer of `MappingLayer`.
+///
+/// Slots are read by the slot table of the layer at the last receive.
+/// Getting an unmapped slot panics, and `try_get` returns an error for it instead.
+#[derive(Debug)]
+pub struct MappingBuffer<L: Layer, S> {
+    buf: L::Buffer,
+    slots: Arc<HashMap<S, L::Slot>>,
+}
+
+impl<L: Layer, S> MappingBuffer<L, S> {
+    /// Gets the buffer of the inner layer.
+    pub fn inner(&self) -> &L::Buffer {
+        &self.buf
+    }
+
+    /// Gets the measured result of slot `n`, or `SlotOutOfRange` if it is unmapped.
+    pub fn try_get(&self, n: &S) -> Result<bool, Error>
+        where S: Eq + Hash + Debug, L::Slot: Clone
+    {
+        match self.slots.get(n) {
+            Some(s) => Ok(self.buf.get(s.clone())),
+            None => Err(Error::SlotOutOfRange(format!("{:?}", n))),
+        }
+    }
+}
+
+impl<L: Layer, S> Measured for MappingBuffer<L, S>
+    where S: Eq + Hash + Debug,
+          L::Slot: Clone,
+{
+    type Slot = S;
+
+    fn get(&self, n: S) -> bool {
+        self.try_get(&n).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::StateVectorLayer;
+
+    #[test]
+    fn mapping() {
+        let mut layer = MappingLayer::with_maps(StateVectorLayer::new(3),
+                                                vec![("a", 2), ("b", 0)],
+                                                vec![("m", 1)]);
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.x("a");
+        ops.measure("a", "m");
+        let mut buf = layer.make_buffer();
+        layer.try_send_receive(ops.as_ref(), &mut buf).unwrap();
+        assert!(buf.get("m"));
+        assert!(buf.inner().get(1));
+
+        layer.map_qubit("a", 1);
+        layer.send_receive(ops.as_ref(), &mut buf);
+        assert!(layer.inner().state()[0b010].norm() > 0.99);
+
+        let mut ops = layer.opsvec();
+        ops.h("c");
+        assert!(matches!(layer.try_send(ops.as_ref()), Err(Error::QubitOutOfRange(_))));
+        let mut ops = layer.opsvec();
+        ops.measure("b", "n");
+        assert!(matches!(layer.try_send(ops.as_ref()), Err(Error::SlotOutOfRange(_))));
+
+        // The buffer made before `map_slot` reads the new slot.
+        layer.map_slot("n", 2);
+        let mut ops = layer.opsvec();
+        ops.measure("a", "n");
+        layer.send_receive(ops.as_ref(), &mut buf);
+        assert!(buf.get("n"));
+        assert!(matches!(buf.try_get(&"o"), Err(Error::SlotOutOfRange(_))));
+    }
+}
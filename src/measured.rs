@@ -1,5 +1,7 @@
 use num_traits::cast::{NumCast, cast};
 
+use crate::Error;
+
 /// Gets the measured result.
 pub trait Measured {
     type Slot;
@@ -54,4 +56,81 @@ pub trait Measured {
             self.get_range_u32(start, start + 32) as u64 | ((self.get_range_u32(start + 32, stop) as u64) << 32)
         }
     }
+
+    /// Gets sequential measured result as u8, or returns an error for invalid range or slot.
+    fn try_get_range_u8(&self, start: usize, stop: usize) -> Result<u8, Error>
+        where Self::Slot : NumCast
+    {
+        check_range(start, stop, 8)?;
+        try_get_bits(self, start, stop).map(|bits| bits as u8)
+    }
+
+    /// Gets sequential measured result as u16, or returns an error for invalid range or slot.
+    fn try_get_range_u16(&self, start: usize, stop: usize) -> Result<u16, Error>
+        where Self::Slot : NumCast
+    {
+        check_range(start, stop, 16)?;
+        try_get_bits(self, start, stop).map(|bits| bits as u16)
+    }
+
+    /// Gets sequential measured result as u32, or returns an error for invalid range or slot.
+    fn try_get_range_u32(&self, start: usize, stop: usize) -> Result<u32, Error>
+        where Self::Slot : NumCast
+    {
+        check_range(start, stop, 32)?;
+        try_get_bits(self, start, stop).map(|bits| bits as u32)
+    }
+
+    /// Gets sequential measured result as u64, or returns an error for invalid range or slot.
+    fn try_get_range_u64(&self, start: usize, stop: usize) -> Result<u64, Error>
+        where Self::Slot : NumCast
+    {
+        check_range(start, stop, 64)?;
+        try_get_bits(self, start, stop)
+    }
+}
+
+fn check_range(start: usize, stop: usize, bits: usize) -> Result<(), Error> {
+    if start <= stop && stop - start <= bits {
+        Ok(())
+    } else {
+        Err(Error::InvalidRange { start, stop })
+    }
+}
+
+/// Gets sequential measured result, converting each index to a slot fallibly.
+fn try_get_bits<M: Measured + ?Sized>(measured: &M, start: usize, stop: usize) -> Result<u64, Error>
+    where M::Slot : NumCast
+{
+    let mut result = 0;
+    for i in start..stop {
+        let slot = cast(i).ok_or_else(|| Error::SlotOutOfRange(i.to_string()))?;
+        result |= (measured.get(slot) as u64) << (i - start);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NarrowBits(u8);
+
+    impl Measured for NarrowBits {
+        type Slot = u8;
+
+        fn get(&self, n: u8) -> bool {
+            n < 8 && self.0 & (1 << n) != 0
+        }
+    }
+
+    #[test]
+    fn try_get_range_narrow_slot() {
+        let bits = NarrowBits(0b1010_0110);
+        assert_eq!(bits.try_get_range_u8(1, 7).unwrap(), 0b01_0011);
+        assert_eq!(bits.try_get_range_u16(0, 16).unwrap(), 0b1010_0110);
+        assert!(matches!(bits.try_get_range_u8(250, 258), Err(Error::SlotOutOfRange(_))));
+        assert!(matches!(bits.try_get_range_u64(255, 257), Err(Error::SlotOutOfRange(_))));
+        assert!(matches!(bits.try_get_range_u8(0, 9), Err(Error::InvalidRange { start: 0, stop: 9 })));
+    }
 }
@@ -1,7 +1,8 @@
 //! Traits for operations.
 
 use crate::Layer;
-use crate::gates::{PauliGate, HGate, SGate, TGate, CXGate};
+use crate::gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                   CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate};
 
 mod opsvec;
 pub use opsvec::OpsVec;
@@ -9,6 +10,9 @@ pub use opsvec::OpsVec;
 mod opargs;
 pub use opargs::{opid, OpArgs};
 
+mod params;
+pub use params::{Param, ParamOpsVec};
+
 /// Provides operations for initialize and measurement.
 pub trait Operation<L> where L: Layer + ?Sized {
     fn initialize() -> Self;
@@ -43,3 +47,54 @@ pub trait TOperation<L> where L: Layer + TGate + ?Sized {
 pub trait CXOperation<L> where L: Layer + CXGate + ?Sized {
     fn cx(c: L::Qubit, t: L::Qubit) -> Self;
 }
+
+/// Provides operations for X, Y and Z rotation gates.
+///
+/// `rx(q, theta)` is exp(-iθX/2), and so on.
+pub trait RotationOperation<L> where L: Layer + RotationGate + ?Sized {
+    fn rx(q: L::Qubit, theta: f64) -> Self;
+    fn ry(q: L::Qubit, theta: f64) -> Self;
+    fn rz(q: L::Qubit, theta: f64) -> Self;
+}
+
+/// Provides operation for generic single qubit gate U3(θ, φ, λ) = RZ(φ) RY(θ) RZ(λ), up to global phase.
+pub trait U3Operation<L> where L: Layer + U3Gate + ?Sized {
+    fn u3(q: L::Qubit, theta: f64, phi: f64, lambda: f64) -> Self;
+}
+
+/// Provides operations for CZ gate.
+pub trait CZOperation<L> where L: Layer + CZGate + ?Sized {
+    fn cz(c: L::Qubit, t: L::Qubit) -> Self;
+}
+
+/// Provides operations for SWAP gate.
+pub trait SwapOperation<L> where L: Layer + SwapGate + ?Sized {
+    fn swap(a: L::Qubit, b: L::Qubit) -> Self;
+}
+
+/// Provides operations for Toffoli (CCX) gate.
+pub trait CCXOperation<L> where L: Layer + CCXGate + ?Sized {
+    fn ccx(c1: L::Qubit, c2: L::Qubit, t: L::Qubit) -> Self;
+}
+
+/// Provides operations for CY gate.
+pub trait CYOperation<L> where L: Layer + CYGate + ?Sized {
+    fn cy(c: L::Qubit, t: L::Qubit) -> Self;
+}
+
+/// Provides operations for iSWAP gate.
+pub trait ISwapOperation<L> where L: Layer + ISwapGate + ?Sized {
+    fn iswap(a: L::Qubit, b: L::Qubit) -> Self;
+}
+
+/// Provides operations for resetting a qubit to |0> without touching other qubits.
+pub trait ResetOperation<L> where L: Layer + ResetGate + ?Sized {
+    fn reset(q: L::Qubit) -> Self;
+}
+
+/// Provides operations which are applied only if measured slots hold a value.
+///
+/// Slot `slots[i]` is compared with bit `i` of `value`.
+pub trait ConditionalOperation<L> where L: Layer + ConditionalGate + ?Sized {
+    fn if_slots(slots: Vec<L::Slot>, value: u64, op: Self) -> Self;
+}
@@ -2,8 +2,12 @@ use std::any::Any;
 use std::fmt::Debug;
 
 use crate::Layer;
-use crate::gates::{PauliGate, HGate, SGate, TGate, CXGate};
-use crate::operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation};
+use crate::gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                   CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate};
+use crate::operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation,
+                        RotationOperation, U3Operation,
+                        CZOperation, SwapOperation, CCXOperation, CYOperation, ISwapOperation,
+                        ConditionalOperation, ResetOperation};
 
 /// Builtin operation IDs.
 pub mod opid {
@@ -29,6 +33,28 @@ pub mod opid {
     pub const TDG: u16 = 10;
     /// CNOT gate
     pub const CX: u16 = 11;
+    /// X rotation gate
+    pub const RX: u16 = 12;
+    /// Y rotation gate
+    pub const RY: u16 = 13;
+    /// Z rotation gate
+    pub const RZ: u16 = 14;
+    /// Generic single qubit gate U3(θ, φ, λ)
+    pub const U3: u16 = 15;
+    /// CZ gate
+    pub const CZ: u16 = 16;
+    /// SWAP gate
+    pub const SWAP: u16 = 17;
+    /// Toffoli gate
+    pub const CCX: u16 = 18;
+    /// CY gate
+    pub const CY: u16 = 19;
+    /// iSWAP gate
+    pub const ISWAP: u16 = 20;
+    /// Operation conditioned on measured slots
+    pub const IF: u16 = 21;
+    /// Reset of a qubit
+    pub const RESET: u16 = 22;
     /// When library user defines new operation ID,
     /// the value should greater than or equal to this value.
     pub const USERDEF: u16 = 256;
@@ -40,11 +66,124 @@ pub enum OpArgs<L: Layer + ?Sized> {
     Empty(u16),
     Q(u16, L::Qubit),
     QQ(u16, L::Qubit, L::Qubit),
+    QQQ(u16, L::Qubit, L::Qubit, L::Qubit),
     QS(u16, L::Qubit, L::Slot),
     QF(u16, L::Qubit, f32),
     QD(u16, L::Qubit, f64),
     QFF(u16, L::Qubit, f32, f32),
+    QDDD(u16, L::Qubit, f64, f64, f64),
     Var(u16, Box<dyn Any + Send>),
+    /// `If(id, slots, value, op)` applies `op` only if slot `slots[i]` holds bit `i` of `value` for every `i`.
+    If(u16, Vec<L::Slot>, u64, Box<OpArgs<L>>),
+}
+
+impl<L: Layer + ?Sized> OpArgs<L> {
+    /// Gets the operation ID.
+    pub fn id(&self) -> u16 {
+        match self {
+            OpArgs::Empty(id) |
+            OpArgs::Q(id, _) |
+            OpArgs::QQ(id, _, _) |
+            OpArgs::QQQ(id, _, _, _) |
+            OpArgs::QS(id, _, _) |
+            OpArgs::QF(id, _, _) |
+            OpArgs::QD(id, _, _) |
+            OpArgs::QFF(id, _, _, _) |
+            OpArgs::QDDD(id, _, _, _, _) |
+            OpArgs::Var(id, _) |
+            OpArgs::If(id, _, _, _) => *id,
+        }
+    }
+
+    /// Converts into the operation for other layer by converting qubits and slots.
+    pub fn map<M, FQ, FS>(self, mut fq: FQ, mut fs: FS) -> OpArgs<M>
+        where M: Layer + ?Sized,
+              FQ: FnMut(L::Qubit) -> M::Qubit,
+              FS: FnMut(L::Slot) -> M::Slot,
+    {
+        self.map_mut(&mut fq, &mut fs)
+    }
+
+    fn map_mut<M, FQ, FS>(self, fq: &mut FQ, fs: &mut FS) -> OpArgs<M>
+        where M: Layer + ?Sized,
+              FQ: FnMut(L::Qubit) -> M::Qubit,
+              FS: FnMut(L::Slot) -> M::Slot,
+    {
+        match self {
+            OpArgs::Empty(id) => OpArgs::Empty(id),
+            OpArgs::Q(id, q) => OpArgs::Q(id, fq(q)),
+            OpArgs::QQ(id, q1, q2) => OpArgs::QQ(id, fq(q1), fq(q2)),
+            OpArgs::QQQ(id, q1, q2, q3) => OpArgs::QQQ(id, fq(q1), fq(q2), fq(q3)),
+            OpArgs::QS(id, q, s) => OpArgs::QS(id, fq(q), fs(s)),
+            OpArgs::QF(id, q, x) => OpArgs::QF(id, fq(q), x),
+            OpArgs::QD(id, q, x) => OpArgs::QD(id, fq(q), x),
+            OpArgs::QFF(id, q, x, y) => OpArgs::QFF(id, fq(q), x, y),
+            OpArgs::QDDD(id, q, x, y, z) => OpArgs::QDDD(id, fq(q), x, y, z),
+            OpArgs::Var(id, v) => OpArgs::Var(id, v),
+            OpArgs::If(id, slots, value, op) => {
+                let slots = slots.into_iter().map(&mut *fs).collect();
+                OpArgs::If(id, slots, value, Box::new(op.map_mut(fq, fs)))
+            },
+        }
+    }
+
+    /// Converts into the operation for other layer by fallible conversion of qubits and slots.
+    pub fn try_map<M, E, FQ, FS>(self, mut fq: FQ, mut fs: FS) -> Result<OpArgs<M>, E>
+        where M: Layer + ?Sized,
+              FQ: FnMut(L::Qubit) -> Result<M::Qubit, E>,
+              FS: FnMut(L::Slot) -> Result<M::Slot, E>,
+    {
+        self.try_map_mut(&mut fq, &mut fs)
+    }
+
+    fn try_map_mut<M, E, FQ, FS>(self, fq: &mut FQ, fs: &mut FS) -> Result<OpArgs<M>, E>
+        where M: Layer + ?Sized,
+              FQ: FnMut(L::Qubit) -> Result<M::Qubit, E>,
+              FS: FnMut(L::Slot) -> Result<M::Slot, E>,
+    {
+        Ok(match self {
+            OpArgs::Empty(id) => OpArgs::Empty(id),
+            OpArgs::Q(id, q) => OpArgs::Q(id, fq(q)?),
+            OpArgs::QQ(id, q1, q2) => OpArgs::QQ(id, fq(q1)?, fq(q2)?),
+            OpArgs::QQQ(id, q1, q2, q3) => OpArgs::QQQ(id, fq(q1)?, fq(q2)?, fq(q3)?),
+            OpArgs::QS(id, q, s) => OpArgs::QS(id, fq(q)?, fs(s)?),
+            OpArgs::QF(id, q, x) => OpArgs::QF(id, fq(q)?, x),
+            OpArgs::QD(id, q, x) => OpArgs::QD(id, fq(q)?, x),
+            OpArgs::QFF(id, q, x, y) => OpArgs::QFF(id, fq(q)?, x, y),
+            OpArgs::QDDD(id, q, x, y, z) => OpArgs::QDDD(id, fq(q)?, x, y, z),
+            OpArgs::Var(id, v) => OpArgs::Var(id, v),
+            OpArgs::If(id, slots, value, op) => {
+                let slots = slots.into_iter().map(&mut *fs).collect::<Result<_, E>>()?;
+                OpArgs::If(id, slots, value, Box::new(op.try_map_mut(fq, fs)?))
+            },
+        })
+    }
+
+    /// Converts into the operation for other layer which has same qubit and slot types.
+    pub fn cast<M>(self) -> OpArgs<M>
+        where M: Layer<Qubit=L::Qubit, Slot=L::Slot> + ?Sized
+    {
+        self.map(|q| q, |s| s)
+    }
+
+    /// Clones the operation. Returns `None` if it has `Var`, whose value can't be cloned.
+    pub fn try_clone(&self) -> Option<Self>
+        where L::Qubit: Clone, L::Slot: Clone
+    {
+        Some(match self {
+            OpArgs::Empty(id) => OpArgs::Empty(*id),
+            OpArgs::Q(id, q) => OpArgs::Q(*id, q.clone()),
+            OpArgs::QQ(id, q1, q2) => OpArgs::QQ(*id, q1.clone(), q2.clone()),
+            OpArgs::QQQ(id, q1, q2, q3) => OpArgs::QQQ(*id, q1.clone(), q2.clone(), q3.clone()),
+            OpArgs::QS(id, q, s) => OpArgs::QS(*id, q.clone(), s.clone()),
+            OpArgs::QF(id, q, x) => OpArgs::QF(*id, q.clone(), *x),
+            OpArgs::QD(id, q, x) => OpArgs::QD(*id, q.clone(), *x),
+            OpArgs::QFF(id, q, x, y) => OpArgs::QFF(*id, q.clone(), *x, *y),
+            OpArgs::QDDD(id, q, x, y, z) => OpArgs::QDDD(*id, q.clone(), *x, *y, *z),
+            OpArgs::Var(_, _) => return None,
+            OpArgs::If(id, slots, value, op) => OpArgs::If(*id, slots.clone(), *value, Box::new(op.try_clone()?)),
+        })
+    }
 }
 
 impl<L> Operation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + ?Sized {
@@ -102,3 +241,65 @@ impl<L> CXOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + CXGat
         OpArgs::QQ(opid::CX, c, t)
     }
 }
+
+impl<L> RotationOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + RotationGate + ?Sized {
+    fn rx(q: L::Qubit, theta: f64) -> OpArgs<L> {
+        OpArgs::QD(opid::RX, q, theta)
+    }
+
+    fn ry(q: L::Qubit, theta: f64) -> OpArgs<L> {
+        OpArgs::QD(opid::RY, q, theta)
+    }
+
+    fn rz(q: L::Qubit, theta: f64) -> OpArgs<L> {
+        OpArgs::QD(opid::RZ, q, theta)
+    }
+}
+
+impl<L> U3Operation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + U3Gate + ?Sized {
+    fn u3(q: L::Qubit, theta: f64, phi: f64, lambda: f64) -> OpArgs<L> {
+        OpArgs::QDDD(opid::U3, q, theta, phi, lambda)
+    }
+}
+
+impl<L> CZOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + CZGate + ?Sized {
+    fn cz(c: L::Qubit, t: L::Qubit) -> OpArgs<L> {
+        OpArgs::QQ(opid::CZ, c, t)
+    }
+}
+
+impl<L> SwapOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + SwapGate + ?Sized {
+    fn swap(a: L::Qubit, b: L::Qubit) -> OpArgs<L> {
+        OpArgs::QQ(opid::SWAP, a, b)
+    }
+}
+
+impl<L> CCXOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + CCXGate + ?Sized {
+    fn ccx(c1: L::Qubit, c2: L::Qubit, t: L::Qubit) -> OpArgs<L> {
+        OpArgs::QQQ(opid::CCX, c1, c2, t)
+    }
+}
+
+impl<L> CYOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + CYGate + ?Sized {
+    fn cy(c: L::Qubit, t: L::Qubit) -> OpArgs<L> {
+        OpArgs::QQ(opid::CY, c, t)
+    }
+}
+
+impl<L> ISwapOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + ISwapGate + ?Sized {
+    fn iswap(a: L::Qubit, b: L::Qubit) -> OpArgs<L> {
+        OpArgs::QQ(opid::ISWAP, a, b)
+    }
+}
+
+impl<L> ResetOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + ResetGate + ?Sized {
+    fn reset(q: L::Qubit) -> OpArgs<L> {
+        OpArgs::Q(opid::RESET, q)
+    }
+}
+
+impl<L> ConditionalOperation<L> for OpArgs<L> where L: Layer<Operation=OpArgs<L>> + ConditionalGate + ?Sized {
+    fn if_slots(slots: Vec<L::Slot>, value: u64, op: OpArgs<L>) -> OpArgs<L> {
+        OpArgs::If(opid::IF, slots, value, Box::new(op))
+    }
+}
@@ -1,8 +1,12 @@
 use std::convert::{AsRef, AsMut};
 
 use crate::Layer;
-use crate::gates::{PauliGate, HGate, SGate, TGate, CXGate};
-use crate::operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation};
+use crate::gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                   CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate};
+use crate::operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation,
+                        RotationOperation, U3Operation,
+                        CZOperation, SwapOperation, CCXOperation, CYOperation, ISwapOperation,
+                        ConditionalOperation, ResetOperation};
 
 /// Vec wrapper for building slice of `Operation`s.
 #[derive(Debug)]
@@ -56,6 +60,12 @@ impl<L> OpsVec<L> where L: Layer + ?Sized {
     }
 }
 
+impl<L: Layer + ?Sized> Default for OpsVec<L> {
+    fn default() -> Self {
+        OpsVec::new()
+    }
+}
+
 impl<L: Layer + ?Sized> AsRef<[L::Operation]> for OpsVec<L> {
     fn as_ref(&self) -> &[L::Operation] {
         self.as_slice()
@@ -122,3 +132,83 @@ impl<L> OpsVec<L> where L: Layer + CXGate + ?Sized, L::Operation: CXOperation<L>
         self.inner.push(L::Operation::cx(c, t));
     }
 }
+
+impl<L> OpsVec<L> where L: Layer + RotationGate + ?Sized, L::Operation: RotationOperation<L> {
+    pub fn rx(&mut self, q: <L as Layer>::Qubit, theta: f64) {
+        self.inner.push(L::Operation::rx(q, theta));
+    }
+
+    pub fn ry(&mut self, q: <L as Layer>::Qubit, theta: f64) {
+        self.inner.push(L::Operation::ry(q, theta));
+    }
+
+    pub fn rz(&mut self, q: <L as Layer>::Qubit, theta: f64) {
+        self.inner.push(L::Operation::rz(q, theta));
+    }
+}
+
+impl<L> OpsVec<L> where L: Layer + U3Gate + ?Sized, L::Operation: U3Operation<L> {
+    pub fn u3(&mut self, q: <L as Layer>::Qubit, theta: f64, phi: f64, lambda: f64) {
+        self.inner.push(L::Operation::u3(q, theta, phi, lambda));
+    }
+}
+
+impl<L> OpsVec<L> where L: Layer + CZGate + ?Sized, L::Operation: CZOperation<L> {
+    pub fn cz(&mut self, c: <L as Layer>::Qubit, t: <L as Layer>::Qubit) {
+        self.inner.push(L::Operation::cz(c, t));
+    }
+}
+
+impl<L> OpsVec<L> where L: Layer + SwapGate + ?Sized, L::Operation: SwapOperation<L> {
+    pub fn swap(&mut self, a: <L as Layer>::Qubit, b: <L as Layer>::Qubit) {
+        self.inner.push(L::Operation::swap(a, b));
+    }
+}
+
+impl<L> OpsVec<L> where L: Layer + CCXGate + ?Sized, L::Operation: CCXOperation<L> {
+    pub fn ccx(&mut self, c1: <L as Layer>::Qubit, c2: <L as Layer>::Qubit, t: <L as Layer>::Qubit) {
+        self.inner.push(L::Operation::ccx(c1, c2, t));
+    }
+}
+
+impl<L> OpsVec<L> where L: Layer + CYGate + ?Sized, L::Operation: CYOperation<L> {
+    pub fn cy(&mut self, c: <L as Layer>::Qubit, t: <L as Layer>::Qubit) {
+        self.inner.push(L::Operation::cy(c, t));
+    }
+}
+
+impl<L> OpsVec<L> where L: Layer + ISwapGate + ?Sized, L::Operation: ISwapOperation<L> {
+    pub fn iswap(&mut self, a: <L as Layer>::Qubit, b: <L as Layer>::Qubit) {
+        self.inner.push(L::Operation::iswap(a, b));
+    }
+}
+
+impl<L> OpsVec<L> where L: Layer + ResetGate + ?Sized, L::Operation: ResetOperation<L> {
+    pub fn reset(&mut self, q: <L as Layer>::Qubit) {
+        self.inner.push(L::Operation::reset(q));
+    }
+}
+
+impl<L> OpsVec<L>
+    where L: Layer + ConditionalGate + ?Sized,
+          L::Operation: ConditionalOperation<L>,
+          L::Slot: Clone,
+{
+    /// Adds operations added by `f`, which are applied only if slot `s` holds `value`.
+    pub fn if_slot<F: FnOnce(&mut OpsVec<L>)>(&mut self, s: L::Slot, value: bool, f: F) {
+        self.if_slots(vec![s], value as u64, f);
+    }
+
+    /// Adds operations added by `f`, which are applied only if slot `slots[i]` holds bit `i` of `value`.
+    ///
+    /// Each operation is conditioned separately, so `f` must not measure into `slots`, and
+    /// must not initialize, which clears all slots.
+    /// Simulators return `InvalidOperation` for such operations.
+    pub fn if_slots<F: FnOnce(&mut OpsVec<L>)>(&mut self, slots: Vec<L::Slot>, value: u64, f: F) {
+        let mut ops = OpsVec::new();
+        f(&mut ops);
+        for op in ops.into_vec() {
+            self.inner.push(L::Operation::if_slots(slots.clone(), value, op));
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Add, Deref, Mul, Neg};
+
+use crate::{Error, Layer, OpsVec};
+use crate::gates::{RotationGate, U3Gate};
+use crate::operations::{RotationOperation, U3Operation};
+
+/// Angle of a parametric gate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    /// Concrete value.
+    Value(f64),
+    /// `scale * symbol + offset`, where the symbol is bound later.
+    Symbol { name: String, scale: f64, offset: f64 },
+}
+
+impl Param {
+    /// Makes a symbol.
+    pub fn symbol<S: Into<String>>(name: S) -> Self {
+        Param::Symbol { name: name.into(), scale: 1.0, offset: 0.0 }
+    }
+
+    /// Evaluates the parameter by given values of symbols.
+    pub fn eval(&self, params: &HashMap<String, f64>) -> Result<f64, Error> {
+        match self {
+            Param::Value(x) => Ok(*x),
+            Param::Symbol { name, scale, offset } => match params.get(name) {
+                Some(x) => Ok(scale * x + offset),
+                None => Err(Error::UnboundParameter(name.clone())),
+            },
+        }
+    }
+}
+
+impl From<f64> for Param {
+    fn from(x: f64) -> Self {
+        Param::Value(x)
+    }
+}
+
+impl From<&str> for Param {
+    fn from(name: &str) -> Self {
+        Param::symbol(name)
+    }
+}
+
+impl From<String> for Param {
+    fn from(name: String) -> Self {
+        Param::symbol(name)
+    }
+}
+
+impl Mul<f64> for Param {
+    type Output = Param;
+
+    fn mul(self, k: f64) -> Param {
+        match self {
+            Param::Value(x) => Param::Value(x * k),
+            Param::Symbol { name, scale, offset } => Param::Symbol { name, scale: scale * k, offset: offset * k },
+        }
+    }
+}
+
+impl Add<f64> for Param {
+    type Output = Param;
+
+    fn add(self, a: f64) -> Param {
+        match self {
+            Param::Value(x) => Param::Value(x + a),
+            Param::Symbol { name, scale, offset } => Param::Symbol { name, scale, offset: offset + a },
+        }
+    }
+}
+
+impl Neg for Param {
+    type Output = Param;
+
+    fn neg(self) -> Param {
+        self * -1.0
+    }
+}
+
+/// A parametric operation in `ParamOpsVec`, which is remade when parameters are bound.
+struct Hole<L: Layer + ?Sized> {
+    index: usize,
+    qubit: L::Qubit,
+    params: Vec<Param>,
+    make: fn(L::Qubit, &[f64]) -> L::Operation,
+}
+
+/// `OpsVec` whose parametric gates may refer to named symbols.
+///
+/// Non-parametric operations are added by `append`, and operations are read through
+/// `Deref` to `OpsVec`. Operations can't be removed or reordered except by `clear`,
+/// so positions of parametric operations are kept.
+/// `bind` rewrites only the parametric operations in place, so the same circuit
+/// can be evaluated with different parameters without rebuilding it.
+/// Operations with unbound symbols have NaN angles, which simulators reject as `InvalidOperation`.
+pub struct ParamOpsVec<L: Layer + ?Sized> {
+    ops: OpsVec<L>,
+    holes: Vec<Hole<L>>,
+}
+
+impl<L: Layer + ?Sized> fmt::Debug for ParamOpsVec<L>
+    where L::Operation: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params: Vec<_> = self.holes.iter().map(|h| (h.index, &h.params)).collect();
+        f.debug_struct("ParamOpsVec").field("ops", &self.ops.as_slice()).field("params", &params).finish()
+    }
+}
+
+impl<L: Layer + ?Sized> ParamOpsVec<L> {
+    pub fn new() -> Self {
+        ParamOpsVec { ops: OpsVec::new(), holes: vec![] }
+    }
+
+    /// Gets the names of symbols, sorted and deduplicated.
+    pub fn symbols(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.holes.iter().flat_map(|h| h.params.iter()).filter_map(|p| match p {
+            Param::Value(_) => None,
+            Param::Symbol { name, .. } => Some(name.as_str()),
+        }).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Clears operations and parameters.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+        self.holes.clear();
+    }
+
+    /// Moves all operations of `ops` to the end, leaving `ops` empty.
+    pub fn append(&mut self, ops: &mut OpsVec<L>) {
+        self.ops.as_mut_vec().append(ops.as_mut_vec());
+    }
+
+    /// Takes the operations. Parametric operations keep the last bound values.
+    pub fn into_opsvec(self) -> OpsVec<L> {
+        self.ops
+    }
+
+    fn push(&mut self, qubit: L::Qubit, params: Vec<Param>, make: fn(L::Qubit, &[f64]) -> L::Operation)
+        where L::Qubit: Clone
+    {
+        let values: Vec<_> = params.iter().map(|p| match p {
+            Param::Value(x) => *x,
+            Param::Symbol { .. } => f64::NAN,
+        }).collect();
+        let index = self.ops.len();
+        self.ops.as_mut_vec().push(make(qubit.clone(), &values));
+        if params.iter().any(|p| matches!(p, Param::Symbol { .. })) {
+            self.holes.push(Hole { index, qubit, params, make });
+        }
+    }
+}
+
+impl<L: Layer + ?Sized> ParamOpsVec<L> where L::Qubit: Clone {
+    /// Binds the values of symbols and returns the concrete operations.
+    ///
+    /// Nothing is changed when an error is returned.
+    pub fn bind(&mut self, params: &HashMap<String, f64>) -> Result<&OpsVec<L>, Error> {
+        for p in self.holes.iter().flat_map(|h| h.params.iter()) {
+            p.eval(params)?;
+        }
+        let mut values = vec![];
+        for hole in &self.holes {
+            values.clear();
+            values.extend(hole.params.iter().map(|p| p.eval(params).unwrap()));
+            self.ops.as_mut_slice()[hole.index] = (hole.make)(hole.qubit.clone(), &values);
+        }
+        Ok(&self.ops)
+    }
+}
+
+impl<L> ParamOpsVec<L> where L: Layer + RotationGate + ?Sized, L::Operation: RotationOperation<L>, L::Qubit: Clone {
+    pub fn rx<P: Into<Param>>(&mut self, q: L::Qubit, theta: P) {
+        self.push(q, vec![theta.into()], |q, v| L::Operation::rx(q, v[0]));
+    }
+
+    pub fn ry<P: Into<Param>>(&mut self, q: L::Qubit, theta: P) {
+        self.push(q, vec![theta.into()], |q, v| L::Operation::ry(q, v[0]));
+    }
+
+    pub fn rz<P: Into<Param>>(&mut self, q: L::Qubit, theta: P) {
+        self.push(q, vec![theta.into()], |q, v| L::Operation::rz(q, v[0]));
+    }
+}
+
+impl<L> ParamOpsVec<L> where L: Layer + U3Gate + ?Sized, L::Operation: U3Operation<L>, L::Qubit: Clone {
+    pub fn u3<P1, P2, P3>(&mut self, q: L::Qubit, theta: P1, phi: P2, lambda: P3)
+        where P1: Into<Param>, P2: Into<Param>, P3: Into<Param>
+    {
+        self.push(q, vec![theta.into(), phi.into(), lambda.into()], |q, v| L::Operation::u3(q, v[0], v[1], v[2]));
+    }
+}
+
+impl<L: Layer + ?Sized> Default for ParamOpsVec<L> {
+    fn default() -> Self {
+        ParamOpsVec::new()
+    }
+}
+
+impl<L: Layer + ?Sized> Deref for ParamOpsVec<L> {
+    type Target = OpsVec<L>;
+
+    fn deref(&self) -> &OpsVec<L> {
+        &self.ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use crate::Measured;
+    use crate::sim::StateVectorLayer;
+
+    #[test]
+    fn bind() {
+        let mut sim = StateVectorLayer::new(2);
+        let mut ops = ParamOpsVec::<StateVectorLayer>::new();
+        let mut fixed = OpsVec::new();
+        fixed.initialize();
+        ops.append(&mut fixed);
+        ops.rx(0, "theta");
+        ops.ry(1, Param::symbol("theta") * 0.5 + PI / 2.0);
+        ops.rz(1, 1.0);
+        fixed.measure(0, 0);
+        ops.append(&mut fixed);
+        assert!(fixed.is_empty());
+        assert_eq!(ops.symbols(), vec!["theta"]);
+        assert!(matches!(ops.bind(&HashMap::new()), Err(Error::UnboundParameter(_))));
+
+        let mut buf = sim.make_buffer();
+        for (theta, expected) in [(0.0, false), (PI, true)] {
+            let params = vec![("theta".to_string(), theta)].into_iter().collect();
+            sim.send_receive(ops.bind(&params).unwrap().as_ref(), &mut buf);
+            assert_eq!(buf.get(0), expected);
+        }
+        assert!(sim.state()[0b11].norm() > 0.99);
+        assert_eq!(ops.len(), 5);
+    }
+}
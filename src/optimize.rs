@@ -0,0 +1,324 @@
+//! Peephole optimization of operations.
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{Error, Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs}};
+
+/// Returns the power of T if the operation is a Z-diagonal phase gate.
+fn phase<L: Layer + ?Sized>(op: &OpArgs<L>) -> Option<u8> {
+    match op {
+        OpArgs::Q(opid::T, _) => Some(1),
+        OpArgs::Q(opid::S, _) => Some(2),
+        OpArgs::Q(opid::Z, _) => Some(4),
+        OpArgs::Q(opid::SDG, _) => Some(6),
+        OpArgs::Q(opid::TDG, _) => Some(7),
+        _ => None,
+    }
+}
+
+/// Returns the qubits of the operation, or None if the operation may act on all qubits.
+fn qubits<L: Layer + ?Sized>(op: &OpArgs<L>) -> Option<Vec<&L::Qubit>> {
+    match op {
+        OpArgs::Q(_, q) | OpArgs::QD(_, q, _) | OpArgs::QDDD(_, q, _, _, _) | OpArgs::QS(opid::MEAS, q, _) => Some(vec![q]),
+        OpArgs::QQ(_, a, b) => Some(vec![a, b]),
+        OpArgs::QQQ(_, a, b, c) => Some(vec![a, b, c]),
+        _ => None,
+    }
+}
+
+/// Combines two operations on the same qubits.
+/// Returns `Some(None)` when they cancel, and None when they can't be combined.
+fn combine<L>(a: &OpArgs<L>, b: &OpArgs<L>) -> Option<Option<OpArgs<L>>>
+    where L: Layer + ?Sized, L::Qubit: Clone + PartialEq
+{
+    match (a, b) {
+        (OpArgs::Q(id1, q1), OpArgs::Q(id2, q2)) if q1 == q2 => {
+            if let (Some(p1), Some(p2)) = (phase(a), phase(b)) {
+                let id = match (p1 + p2) % 8 {
+                    0 => return Some(None),
+                    1 => opid::T,
+                    2 => opid::S,
+                    4 => opid::Z,
+                    6 => opid::SDG,
+                    7 => opid::TDG,
+                    _ => return None,
+                };
+                Some(Some(OpArgs::Q(id, q1.clone())))
+            } else if id1 == id2 && matches!(*id1, opid::H | opid::X | opid::Y) {
+                Some(None)
+            } else {
+                None
+            }
+        },
+        (OpArgs::QQ(opid::CX, c1, t1), OpArgs::QQ(opid::CX, c2, t2)) if c1 == c2 && t1 == t2 => Some(None),
+        (OpArgs::QQ(id1, a1, b1), OpArgs::QQ(id2, a2, b2))
+            if id1 == id2 && matches!(*id1, opid::CZ | opid::SWAP) &&
+               ((a1 == a2 && b1 == b2) || (a1 == b2 && b1 == a2)) => Some(None),
+        _ => None,
+    }
+}
+
+/// Returns true if two operations which share qubits commute.
+fn commutes<L>(a: &OpArgs<L>, b: &OpArgs<L>) -> bool
+    where L: Layer + ?Sized, L::Qubit: PartialEq
+{
+    match (a, b) {
+        (OpArgs::Q(_, q1), OpArgs::Q(_, q2)) => q1 != q2 || (phase(a).is_some() && phase(b).is_some()),
+        (single @ OpArgs::Q(id, q), OpArgs::QQ(opid::CX, c, t)) |
+        (OpArgs::QQ(opid::CX, c, t), single @ OpArgs::Q(id, q)) => {
+            (phase(single).is_some() && q == c) || (*id == opid::X && q == t)
+        },
+        (OpArgs::QQ(opid::CX, c1, t1), OpArgs::QQ(opid::CX, c2, t2)) => c1 != t2 && t1 != c2,
+        _ => false,
+    }
+}
+
+/// Removes redundant gates.
+///
+/// Adjacent inverse pairs are cancelled, and adjacent phase gates are merged
+/// (e.g. `t t` into `s`, `s s` into `z`). Gates are looked ahead past gates which
+/// commute with them, e.g. Z-diagonal gates past CX controls, X past CX targets,
+/// and CX past CX which shares the control or the target.
+///
+/// `Var` operations can't be copied, so they are `UnsupportedOperation`.
+pub fn optimize<L>(ops: &[OpArgs<L>]) -> Result<Vec<OpArgs<L>>, Error>
+    where L: Layer + ?Sized, L::Qubit: Clone + Eq + Hash, L::Slot: Clone
+{
+    // Wires are numbered qubits. Operations which may act on all qubits are on every wire.
+    let mut numbers: HashMap<&L::Qubit, usize> = HashMap::new();
+    let on_qubits: Vec<_> = ops.iter().map(|op| qubits(op).map(|qs| {
+        let mut ws = Vec::with_capacity(qs.len());
+        for q in qs {
+            let n = numbers.len();
+            let w = *numbers.entry(q).or_insert(n);
+            if !ws.contains(&w) {
+                ws.push(w);
+            }
+        }
+        ws
+    })).collect();
+    let n_wires = numbers.len();
+    let wires: Vec<Vec<usize>> = on_qubits.iter().map(|w| w.clone().unwrap_or_else(|| (0..n_wires).collect())).collect();
+
+    // Doubly linked lists of operations on each wire. `prev[i][k]` and `next[i][k]` are
+    // the neighbors of operation `i` on its wire `wires[i][k]`.
+    let mut prev: Vec<Vec<Option<usize>>> = wires.iter().map(|w| vec![None; w.len()]).collect();
+    let mut next = prev.clone();
+    let mut last: Vec<Option<usize>> = vec![None; n_wires];
+    for (i, ws) in wires.iter().enumerate() {
+        for (k, &w) in ws.iter().enumerate() {
+            if let Some(p) = last[w] {
+                let pk = position(&wires[p], w);
+                next[p][pk] = Some(i);
+                prev[i][k] = Some(p);
+            }
+            last[w] = Some(i);
+        }
+    }
+    let remove = |i: usize, prev: &mut Vec<Vec<Option<usize>>>, next: &mut Vec<Vec<Option<usize>>>| {
+        for (k, &w) in wires[i].iter().enumerate() {
+            let (p, n) = (prev[i][k], next[i][k]);
+            if let Some(p) = p {
+                let pk = position(&wires[p], w);
+                next[p][pk] = n;
+            }
+            if let Some(n) = n {
+                let nk = position(&wires[n], w);
+                prev[n][nk] = p;
+            }
+        }
+    };
+
+    let mut ops = ops.iter().map(|op| op.try_clone().ok_or(Error::UnsupportedOperation(op.id())).map(Some))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..ops.len() {
+            let op = match &ops[i] {
+                Some(op) if on_qubits[i].is_some() => op,
+                _ => continue,
+            };
+            // Visits the following operations which share qubits in order.
+            let mut cursors = next[i].clone();
+            while let Some(j) = cursors.iter().flatten().copied().min() {
+                let other = ops[j].as_ref().unwrap();
+                if let Some(combined) = combine(op, other) {
+                    remove(i, &mut prev, &mut next);
+                    ops[i] = None;
+                    if combined.is_none() {
+                        remove(j, &mut prev, &mut next);
+                    }
+                    // A combined gate is on the same qubit, so the links are kept.
+                    ops[j] = combined;
+                    changed = true;
+                    break;
+                }
+                if !commutes(op, other) {
+                    break;
+                }
+                for (k, cursor) in cursors.iter_mut().enumerate() {
+                    if *cursor == Some(j) {
+                        *cursor = next[j][position(&wires[j], wires[i][k])];
+                    }
+                }
+            }
+        }
+    }
+    Ok(ops.into_iter().flatten().collect())
+}
+
+/// Gets the index of wire `w` in `wires`.
+fn position(wires: &[usize], w: usize) -> usize {
+    wires.iter().position(|&x| x == w).unwrap()
+}
+
+/// Layer which optimizes operations by `optimize` before sending them to the inner layer.
+///
+/// Merged phase gates may be S, S† or Z even if they were only T gates, so the inner layer
+/// needs them. Put `DecomposeLayer` under this layer for a backend which doesn't have them.
+#[derive(Debug)]
+pub struct OptimizeLayer<L> {
+    layer: L,
+}
+
+impl<L> OptimizeLayer<L>
+    where L: Layer<Operation=OpArgs<L>>,
+          L::Qubit: Clone + Eq + Hash,
+          L::Slot: Clone,
+{
+    pub fn new(layer: L) -> Self {
+        OptimizeLayer { layer }
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.layer
+    }
+
+    pub fn into_inner(self) -> L {
+        self.layer
+    }
+
+    fn lower(ops: &[OpArgs<Self>]) -> Result<Vec<OpArgs<L>>, Error> {
+        Ok(optimize(ops)?.into_iter().map(OpArgs::cast).collect())
+    }
+}
+
+impl<L> Layer for OptimizeLayer<L>
+    where L: Layer<Operation=OpArgs<L>>,
+          L::Qubit: Clone + Eq + Hash,
+          L::Slot: Clone,
+{
+    type Operation = OpArgs<Self>;
+    type Qubit = L::Qubit;
+    type Slot = L::Slot;
+    type Buffer = L::Buffer;
+    type Requested = L::Requested;
+    type Response = L::Response;
+
+    fn make_buffer(&self) -> L::Buffer {
+        self.layer.make_buffer()
+    }
+
+    fn send(&mut self, ops: &[Self::Operation]) -> L::Requested {
+        self.layer.send(&Self::lower(ops).unwrap_or_else(|e| panic!("{}", e)))
+    }
+
+    fn receive(&mut self, buf: &mut L::Buffer) -> L::Response {
+        self.layer.receive(buf)
+    }
+
+    fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> L::Response {
+        self.layer.send_receive(&Self::lower(ops).unwrap_or_else(|e| panic!("{}", e)), buf)
+    }
+}
+
+impl<L> TryLayer for OptimizeLayer<L>
+    where L: TryLayer<Operation=OpArgs<L>>,
+          L::Qubit: Clone + Eq + Hash,
+          L::Slot: Clone,
+{
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<L::Requested, Error> {
+        self.layer.try_send(&Self::lower(ops)?)
+    }
+
+    fn try_receive(&mut self, buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        self.layer.try_receive(buf)
+    }
+
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        self.layer.try_send_receive(&Self::lower(ops)?, buf)
+    }
+}
+
+macro_rules! impl_gates {
+    ($($gate:ident),*) => {
+        $(
+            impl<L> $gate for OptimizeLayer<L>
+                where L: Layer<Operation=OpArgs<L>> + $gate,
+                      L::Qubit: Clone + Eq + Hash,
+                      L::Slot: Clone {}
+        )*
+    }
+}
+
+impl_gates!(PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+            CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OpsVec, sim::StateVectorLayer};
+
+    fn check(f: impl Fn(&mut OpsVec<StateVectorLayer>), g: impl Fn(&mut OpsVec<StateVectorLayer>)) {
+        let (mut ops, mut expected) = (OpsVec::new(), OpsVec::new());
+        f(&mut ops);
+        g(&mut expected);
+        assert_eq!(format!("{:?}", optimize(ops.as_ref()).unwrap()), format!("{:?}", expected.as_ref()));
+    }
+
+    #[test]
+    fn peephole() {
+        check(|o| { o.h(0); o.h(0); o.t(0); o.t(0); o.x(1); }, |o| { o.s(0); o.x(1); });
+        check(|o| { o.s(0); o.s(0); o.z(0); }, |_| {});
+        check(|o| { o.s(0); o.cx(0, 1); o.sdg(0); }, |o| { o.cx(0, 1); });
+        check(|o| { o.cx(0, 1); o.t(0); o.x(1); o.cx(0, 1); }, |o| { o.t(0); o.x(1); });
+        check(|o| { o.cx(0, 1); o.cx(0, 2); o.cx(0, 1); }, |o| { o.cx(0, 2); });
+        check(|o| { o.t(1); o.cx(0, 1); o.tdg(1); }, |o| { o.t(1); o.cx(0, 1); o.tdg(1); });
+        check(|o| { o.h(0); o.measure(0, 0); o.h(0); }, |o| { o.h(0); o.measure(0, 0); o.h(0); });
+        check(|o| { o.x(0); o.initialize(); o.x(0); }, |o| { o.x(0); o.initialize(); o.x(0); });
+    }
+
+    fn build<L>(ops: &mut OpsVec<L>)
+        where L: Layer<Operation=OpArgs<L>, Qubit=u32> + HGate + SGate + TGate + CXGate
+    {
+        ops.initialize();
+        ops.h(0);
+        ops.t(0);
+        ops.cx(0, 1);
+        ops.t(0);
+        ops.h(1);
+        ops.h(1);
+        ops.cx(0, 1);
+        ops.s(1);
+    }
+
+    #[test]
+    fn layer() {
+        let mut expected = StateVectorLayer::new(2);
+        let mut layer = OptimizeLayer::new(StateVectorLayer::new(2));
+        let mut ops = expected.opsvec();
+        build(&mut ops);
+        expected.send(ops.as_ref());
+        let mut ops = layer.opsvec();
+        build(&mut ops);
+        assert_eq!(optimize(ops.as_ref()).unwrap().len(), 4);
+        layer.send(ops.as_ref());
+        for (a, b) in expected.state().iter().zip(layer.inner().state()) {
+            assert!((a - b).norm() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! OpenQASM 2.0 support.
+
+mod export;
+pub use export::to_qasm;
+
+mod parse;
+pub use parse::{parse, parse_with_reset, ParseError};
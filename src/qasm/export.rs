@@ -0,0 +1,139 @@
+use std::fmt::Write;
+use num_traits::cast::ToPrimitive;
+
+use crate::{Error, Layer, operations::{opid, OpArgs}};
+
+/// Serializes operations into OpenQASM 2.0.
+///
+/// Sizes of `qreg q` and `creg c` are determined by the highest qubit and slot used.
+/// `initialize` is serialized as `reset q;`, and reset of a qubit as `reset q[i];`.
+/// Angles are written in decimal notation, and non-finite angles are `InvalidOperation`.
+///
+/// The output uses more gates of qelib1 than `parse` accepts, so rotations and
+/// multi-qubit gates other than `cx` can't be parsed back.
+pub fn to_qasm<L>(ops: &[OpArgs<L>]) -> Result<String, Error>
+    where L: Layer + ?Sized,
+          L::Qubit: ToPrimitive,
+          L::Slot: ToPrimitive,
+{
+    let qubit = |q: &L::Qubit| q.to_u64().ok_or_else(|| Error::QubitOutOfRange("(not an index)".to_string()));
+    let slot = |s: &L::Slot| s.to_u64().ok_or_else(|| Error::SlotOutOfRange("(not an index)".to_string()));
+    // `Display` of `f64` never uses the exponent notation, which is not a real of OpenQASM 2.0.
+    let angle = |a: f64| if a.is_finite() {
+        Ok(a.to_string())
+    } else {
+        Err(Error::InvalidOperation(format!("angle {} is not finite", a)))
+    };
+
+    let mut body = String::new();
+    let (mut n_qubits, mut n_slots) = (0, 0);
+    for op in ops {
+        match op {
+            OpArgs::Empty(opid::INIT) => body.push_str("reset q;\n"),
+            OpArgs::QS(opid::MEAS, q, s) => {
+                let (q, s) = (qubit(q)?, slot(s)?);
+                n_qubits = n_qubits.max(q + 1);
+                n_slots = n_slots.max(s + 1);
+                writeln!(body, "measure q[{}] -> c[{}];", q, s).unwrap();
+            },
+            OpArgs::Q(id, q) => {
+                let name = match *id {
+                    opid::X => "x",
+                    opid::Y => "y",
+                    opid::Z => "z",
+                    opid::H => "h",
+                    opid::S => "s",
+                    opid::SDG => "sdg",
+                    opid::T => "t",
+                    opid::TDG => "tdg",
+                    opid::RESET => "reset",
+                    _ => return Err(Error::UnsupportedOperation(*id)),
+                };
+                let q = qubit(q)?;
+                n_qubits = n_qubits.max(q + 1);
+                writeln!(body, "{} q[{}];", name, q).unwrap();
+            },
+            OpArgs::QD(id, q, theta) => {
+                let name = match *id {
+                    opid::RX => "rx",
+                    opid::RY => "ry",
+                    opid::RZ => "rz",
+                    _ => return Err(Error::UnsupportedOperation(*id)),
+                };
+                let q = qubit(q)?;
+                n_qubits = n_qubits.max(q + 1);
+                writeln!(body, "{}({}) q[{}];", name, angle(*theta)?, q).unwrap();
+            },
+            OpArgs::QDDD(opid::U3, q, theta, phi, lambda) => {
+                let q = qubit(q)?;
+                n_qubits = n_qubits.max(q + 1);
+                let (theta, phi, lambda) = (angle(*theta)?, angle(*phi)?, angle(*lambda)?);
+                writeln!(body, "u3({},{},{}) q[{}];", theta, phi, lambda, q).unwrap();
+            },
+            OpArgs::QQ(id, a, b) => {
+                let name = match *id {
+                    opid::CX => "cx",
+                    opid::CZ => "cz",
+                    opid::CY => "cy",
+                    opid::SWAP => "swap",
+                    _ => return Err(Error::UnsupportedOperation(*id)),
+                };
+                let (a, b) = (qubit(a)?, qubit(b)?);
+                n_qubits = n_qubits.max(a.max(b) + 1);
+                writeln!(body, "{} q[{}],q[{}];", name, a, b).unwrap();
+            },
+            OpArgs::QQQ(opid::CCX, a, b, c) => {
+                let (a, b, c) = (qubit(a)?, qubit(b)?, qubit(c)?);
+                n_qubits = n_qubits.max(a.max(b).max(c) + 1);
+                writeln!(body, "ccx q[{}],q[{}],q[{}];", a, b, c).unwrap();
+            },
+            _ => return Err(Error::UnsupportedOperation(op.id())),
+        }
+    }
+
+    let mut qasm = String::from("OPENQASM 2.0;\ninclude \"qelib1.inc\";\n");
+    if n_qubits > 0 {
+        writeln!(qasm, "qreg q[{}];", n_qubits).unwrap();
+    } else {
+        // Only `reset q;` may be written, but there is no qubit to reset.
+        body.clear();
+    }
+    if n_slots > 0 {
+        writeln!(qasm, "creg c[{}];", n_slots).unwrap();
+    }
+    qasm.push_str(&body);
+    Ok(qasm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::StateVectorLayer;
+
+    #[test]
+    fn export() {
+        let mut ops = StateVectorLayer::new(3).opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.sdg(2);
+        ops.cx(0, 2);
+        ops.measure(2, 1);
+        assert_eq!(to_qasm(ops.as_ref()).unwrap(), "OPENQASM 2.0;\n\
+                                                   include \"qelib1.inc\";\n\
+                                                   qreg q[3];\n\
+                                                   creg c[2];\n\
+                                                   reset q;\n\
+                                                   h q[0];\n\
+                                                   sdg q[2];\n\
+                                                   cx q[0],q[2];\n\
+                                                   measure q[2] -> c[1];\n");
+        let ops = [OpArgs::<StateVectorLayer>::QD(opid::USERDEF, 0, 1.0)];
+        assert!(matches!(to_qasm(&ops), Err(Error::UnsupportedOperation(opid::USERDEF))));
+
+        let ops = [OpArgs::<StateVectorLayer>::QD(opid::RZ, 0, 1e-7),
+                   OpArgs::QDDD(opid::U3, 0, -2.0, 0.5, 0.0)];
+        assert!(to_qasm(&ops).unwrap().ends_with("rz(0.0000001) q[0];\nu3(-2,0.5,0) q[0];\n"));
+        let ops = [OpArgs::<StateVectorLayer>::QD(opid::RX, 0, f64::NAN)];
+        assert!(matches!(to_qasm(&ops), Err(Error::InvalidOperation(_))));
+    }
+}
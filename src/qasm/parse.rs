@@ -0,0 +1,434 @@
+use std::fmt;
+use num_traits::cast::{NumCast, cast};
+
+use crate::{Layer, OpsVec,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, ResetGate},
+            operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation, ResetOperation}};
+
+/// Adds the reset of a qubit, or `None` if the layer doesn't have reset.
+type Reset<L> = Option<fn(&mut OpsVec<L>, <L as Layer>::Qubit)>;
+
+/// Error while parsing OpenQASM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-origin line number.
+    pub line: usize,
+    /// 1-origin column number.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Int(u64),
+    Real(f64),
+    Str(String),
+    Symbol(&'static str),
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Ident(s) => write!(f, "`{}`", s),
+            TokenKind::Int(n) => write!(f, "`{}`", n),
+            TokenKind::Real(x) => write!(f, "`{}`", x),
+            TokenKind::Str(s) => write!(f, "\"{}\"", s),
+            TokenKind::Symbol(s) => write!(f, "`{}`", s),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 15] = ["->", "==", ";", ",", "[", "]", "(", ")", "{", "}", "+", "-", "*", "/", "^"];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = vec![];
+    for (i, line) in src.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut j = 0;
+        while j < chars.len() {
+            let c = chars[j];
+            let (lno, col) = (i + 1, j + 1);
+            let rest = &chars[j..];
+            let starts_with = |s: &str| s.chars().enumerate().all(|(k, c)| rest.get(k) == Some(&c));
+            let text = |start: usize, len: usize| -> String { chars[j + start..j + start + len].iter().collect() };
+            if c.is_whitespace() {
+                j += 1;
+            } else if starts_with("//") {
+                break;
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let len = chars[j..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '_').count();
+                tokens.push(Token { kind: TokenKind::Ident(text(0, len)), line: lno, column: col });
+                j += len;
+            } else if c.is_ascii_digit() || c == '.' {
+                let len = chars[j..].iter().take_while(|c| c.is_ascii_digit() || **c == '.').count();
+                let mut len = len;
+                // Exponent part.
+                if matches!(chars.get(j + len), Some('e') | Some('E')) {
+                    let sign = matches!(chars.get(j + len + 1), Some('+') | Some('-')) as usize;
+                    let digits = chars[(j + len + 1 + sign).min(chars.len())..].iter().take_while(|c| c.is_ascii_digit()).count();
+                    if digits > 0 {
+                        len += 1 + sign + digits;
+                    }
+                }
+                let text = text(0, len);
+                let kind = if let Ok(n) = text.parse::<u64>() {
+                    TokenKind::Int(n)
+                } else if let Ok(x) = text.parse::<f64>() {
+                    TokenKind::Real(x)
+                } else {
+                    return Err(ParseError { line: lno, column: col, message: format!("invalid number `{}`", text) });
+                };
+                tokens.push(Token { kind, line: lno, column: col });
+                j += len;
+            } else if c == '"' {
+                let len = chars[j + 1..].iter().take_while(|c| **c != '"').count();
+                if j + 1 + len >= chars.len() {
+                    return Err(ParseError { line: lno, column: col, message: "unterminated string".to_string() });
+                }
+                tokens.push(Token { kind: TokenKind::Str(text(1, len)), line: lno, column: col });
+                j += len + 2;
+            } else if let Some(sym) = SYMBOLS.iter().find(|s| starts_with(s)) {
+                tokens.push(Token { kind: TokenKind::Symbol(sym), line: lno, column: col });
+                j += sym.len();
+            } else {
+                return Err(ParseError { line: lno, column: col, message: format!("unexpected character `{}`", c) });
+            }
+        }
+    }
+    let line = src.lines().count().max(1);
+    let column = src.lines().last().map(|l| l.chars().count() + 1).unwrap_or(1);
+    tokens.push(Token { kind: TokenKind::Eof, line, column });
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+struct Register {
+    name: String,
+    offset: u64,
+    size: u64,
+}
+
+/// Operand of a statement.
+#[derive(Debug, Clone, Copy)]
+enum Arg {
+    /// Flattened index of a bit.
+    Bit(u64),
+    /// Offset and size of whole register.
+    Register(u64, u64),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    qregs: Vec<Register>,
+    cregs: Vec<Register>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn next(&mut self) -> Token {
+        let t = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        t
+    }
+
+    fn error<T>(token: &Token, message: String) -> Result<T, ParseError> {
+        Err(ParseError { line: token.line, column: token.column, message })
+    }
+
+    fn expect(&mut self, sym: &'static str) -> Result<(), ParseError> {
+        let t = self.next();
+        if t.kind == TokenKind::Symbol(sym) {
+            Ok(())
+        } else {
+            Self::error(&t, format!("expected `{}`, found {}", sym, t.kind))
+        }
+    }
+
+    fn ident(&mut self) -> Result<(String, Token), ParseError> {
+        let t = self.next();
+        match &t.kind {
+            TokenKind::Ident(s) => Ok((s.clone(), t)),
+            _ => Self::error(&t, format!("expected identifier, found {}", t.kind)),
+        }
+    }
+
+    fn int(&mut self) -> Result<u64, ParseError> {
+        let t = self.next();
+        match t.kind {
+            TokenKind::Int(n) => Ok(n),
+            _ => Self::error(&t, format!("expected integer, found {}", t.kind)),
+        }
+    }
+
+    fn header(&mut self) -> Result<(), ParseError> {
+        if self.peek().kind == TokenKind::Ident("OPENQASM".to_string()) {
+            self.next();
+            let t = self.next();
+            match t.kind {
+                TokenKind::Real(2.0) | TokenKind::Int(2) => {},
+                _ => return Self::error(&t, format!("unsupported OpenQASM version {}", t.kind)),
+            }
+            self.expect(";")?;
+        }
+        Ok(())
+    }
+
+    fn register(&mut self, quantum: bool) -> Result<(), ParseError> {
+        let (name, t) = self.ident()?;
+        self.expect("[")?;
+        let size = self.int()?;
+        self.expect("]")?;
+        self.expect(";")?;
+        if self.qregs.iter().chain(self.cregs.iter()).any(|r| r.name == name) {
+            return Self::error(&t, format!("register `{}` is already declared", name));
+        }
+        let regs = if quantum { &mut self.qregs } else { &mut self.cregs };
+        let offset = regs.last().map(|r| r.offset + r.size).unwrap_or(0);
+        regs.push(Register { name, offset, size });
+        Ok(())
+    }
+
+    fn operand(&mut self, quantum: bool) -> Result<Arg, ParseError> {
+        let (name, t) = self.ident()?;
+        let index = if self.peek().kind == TokenKind::Symbol("[") {
+            self.next();
+            let i = self.int()?;
+            self.expect("]")?;
+            Some(i)
+        } else {
+            None
+        };
+        let regs = if quantum { &self.qregs } else { &self.cregs };
+        let reg = match regs.iter().find(|r| r.name == name) {
+            Some(reg) => reg,
+            None => {
+                let kind = if quantum { "qreg" } else { "creg" };
+                return Self::error(&t, format!("undeclared {} `{}`", kind, name));
+            },
+        };
+        match index {
+            Some(i) if i >= reg.size => {
+                Self::error(&t, format!("index {} is out of range of `{}[{}]`", i, name, reg.size))
+            },
+            Some(i) => Ok(Arg::Bit(reg.offset + i)),
+            None => Ok(Arg::Register(reg.offset, reg.size)),
+        }
+    }
+
+    /// Parses operands separated by `,` or `->`, and returns flattened indices
+    /// for each application. Whole registers are broadcasted.
+    fn operands(&mut self, quantum: &[bool], at: &Token) -> Result<Vec<Vec<u64>>, ParseError> {
+        let mut args = vec![];
+        for (i, q) in quantum.iter().enumerate() {
+            if i > 0 {
+                self.expect(if *q == quantum[i - 1] { "," } else { "->" })?;
+            }
+            args.push(self.operand(*q)?);
+        }
+        self.expect(";")?;
+        let sizes: Vec<u64> = args.iter().filter_map(|a| match a {
+            Arg::Register(_, size) => Some(*size),
+            Arg::Bit(_) => None,
+        }).collect();
+        let n = sizes.first().copied().unwrap_or(1);
+        if sizes.iter().any(|&m| m != n) {
+            return Self::error(at, "sizes of registers are mismatched".to_string());
+        }
+        Ok((0..n).map(|i| args.iter().map(|a| match a {
+            Arg::Bit(b) => *b,
+            Arg::Register(offset, _) => offset + i,
+        }).collect()).collect())
+    }
+}
+
+fn convert<T: NumCast>(n: u64, token: &Token) -> Result<T, ParseError> {
+    cast(n).map_or_else(|| Parser::error(token, format!("index {} cannot be converted", n)), Ok)
+}
+
+/// Parses OpenQASM 2.0 source with qelib1 subset.
+///
+/// Supported statements are `qreg`, `creg`, `x`, `y`, `z`, `h`, `s`, `sdg`, `t`, `tdg`,
+/// `cx`, `measure`, `id` and `barrier`. Registers are flattened in declared order.
+/// `reset` is an error, and `parse_with_reset` parses it for layers which have reset.
+/// Other gates which `to_qasm` writes, such as `rx` and `u3`, are not supported.
+pub fn parse<L>(src: &str) -> Result<OpsVec<L>, ParseError>
+    where L: Layer + PauliGate + HGate + SGate + TGate + CXGate + ?Sized,
+          L::Operation: Operation<L> + PauliOperation<L> + HOperation<L> + SOperation<L> + TOperation<L> + CXOperation<L>,
+          L::Qubit: NumCast,
+          L::Slot: NumCast,
+{
+    parse_ops(src, None)
+}
+
+/// Parses OpenQASM 2.0 source as `parse`, and also parses `reset` into resets of each qubit.
+pub fn parse_with_reset<L>(src: &str) -> Result<OpsVec<L>, ParseError>
+    where L: Layer + PauliGate + HGate + SGate + TGate + CXGate + ResetGate + ?Sized,
+          L::Operation: Operation<L> + PauliOperation<L> + HOperation<L> + SOperation<L> + TOperation<L>
+                        + CXOperation<L> + ResetOperation<L>,
+          L::Qubit: NumCast,
+          L::Slot: NumCast,
+{
+    parse_ops(src, Some(|ops, q| ops.reset(q)))
+}
+
+fn parse_ops<L>(src: &str, reset: Reset<L>) -> Result<OpsVec<L>, ParseError>
+    where L: Layer + PauliGate + HGate + SGate + TGate + CXGate + ?Sized,
+          L::Operation: Operation<L> + PauliOperation<L> + HOperation<L> + SOperation<L> + TOperation<L> + CXOperation<L>,
+          L::Qubit: NumCast,
+          L::Slot: NumCast,
+{
+    let mut p = Parser { tokens: tokenize(src)?, pos: 0, qregs: vec![], cregs: vec![] };
+    let mut ops = OpsVec::new();
+    p.header()?;
+    loop {
+        let t = p.next();
+        let name = match &t.kind {
+            TokenKind::Eof => break,
+            TokenKind::Ident(name) => name.clone(),
+            _ => return Parser::error(&t, format!("expected statement, found {}", t.kind)),
+        };
+        match name.as_str() {
+            "include" => {
+                let f = p.next();
+                match &f.kind {
+                    TokenKind::Str(s) if s == "qelib1.inc" => {},
+                    _ => return Parser::error(&f, format!("unsupported include {}", f.kind)),
+                }
+                p.expect(";")?;
+            },
+            "qreg" => p.register(true)?,
+            "creg" => p.register(false)?,
+            "barrier" => {
+                while !matches!(p.next().kind, TokenKind::Symbol(";") | TokenKind::Eof) {}
+            },
+            "reset" if reset.is_some() => {
+                for args in p.operands(&[true], &t)? {
+                    reset.unwrap()(&mut ops, convert(args[0], &t)?);
+                }
+            },
+            "measure" => {
+                for args in p.operands(&[true, false], &t)? {
+                    ops.measure(convert(args[0], &t)?, convert(args[1], &t)?);
+                }
+            },
+            "id" | "x" | "y" | "z" | "h" | "s" | "sdg" | "t" | "tdg" => {
+                for args in p.operands(&[true], &t)? {
+                    let q = convert(args[0], &t)?;
+                    match name.as_str() {
+                        "x" => ops.x(q),
+                        "y" => ops.y(q),
+                        "z" => ops.z(q),
+                        "h" => ops.h(q),
+                        "s" => ops.s(q),
+                        "sdg" => ops.sdg(q),
+                        "t" => ops.t(q),
+                        "tdg" => ops.tdg(q),
+                        _ => {},
+                    }
+                }
+            },
+            "cx" | "CX" => {
+                for args in p.operands(&[true, true], &t)? {
+                    if args[0] == args[1] {
+                        return Parser::error(&t, "control and target of `cx` are the same qubit".to_string());
+                    }
+                    ops.cx(convert(args[0], &t)?, convert(args[1], &t)?);
+                }
+            },
+            _ => return Parser::error(&t, format!("unsupported statement `{}`", name)),
+        }
+    }
+    Ok(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Measured, qasm::to_qasm, sim::StateVectorLayer};
+
+    #[test]
+    fn roundtrip() {
+        let src = "OPENQASM 2.0;\n\
+                   include \"qelib1.inc\";\n\
+                   qreg a[2];\n\
+                   qreg b[2];\n\
+                   creg c[4];\n\
+                   x a; // broadcast\n\
+                   barrier a, b;\n\
+                   cx a, b;\n\
+                   h b[1];\n\
+                   h b[1];\n\
+                   measure a -> c[0];\n\
+                   measure b[1] -> c[3];\n";
+        let ops = parse::<StateVectorLayer>(src).unwrap();
+        assert_eq!(to_qasm(ops.as_ref()).unwrap(), "OPENQASM 2.0;\n\
+                                                   include \"qelib1.inc\";\n\
+                                                   qreg q[4];\n\
+                                                   creg c[4];\n\
+                                                   x q[0];\n\
+                                                   x q[1];\n\
+                                                   cx q[0],q[2];\n\
+                                                   cx q[1],q[3];\n\
+                                                   h q[3];\n\
+                                                   h q[3];\n\
+                                                   measure q[0] -> c[0];\n\
+                                                   measure q[1] -> c[0];\n\
+                                                   measure q[3] -> c[3];\n");
+        let mut sim = StateVectorLayer::new(4);
+        let mut buf = sim.make_buffer();
+        sim.send_receive(ops.as_ref(), &mut buf);
+        assert_eq!(buf.get_range_u8(0, 4), 0b1001);
+
+        let ops = parse_with_reset::<StateVectorLayer>("qreg q[2];\nreset q;\nreset q[1];").unwrap();
+        assert!(to_qasm(ops.as_ref()).unwrap().ends_with("reset q[0];\nreset q[1];\nreset q[1];\n"));
+        let ops = parse_with_reset::<StateVectorLayer>("qreg a[1];\nqreg b[2];\nreset b;").unwrap();
+        assert!(to_qasm(ops.as_ref()).unwrap().ends_with("reset q[1];\nreset q[2];\n"));
+        // Measured results are kept by reset.
+        let src = "qreg q[1];\ncreg c[1];\nx q[0];\nmeasure q[0] -> c[0];\nreset q;";
+        let ops = parse_with_reset::<StateVectorLayer>(src).unwrap();
+        let mut sim = StateVectorLayer::new(1);
+        sim.send_receive(ops.as_ref(), &mut buf);
+        assert!(buf.get(0));
+        let err = parse::<StateVectorLayer>(src).unwrap_err();
+        assert_eq!((err.line, err.column, err.message.as_str()), (5, 1, "unsupported statement `reset`"));
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse::<StateVectorLayer>("qreg q[2];\nh q[0];\n  rz(0.1) q[1];").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+        assert_eq!(err.message, "unsupported statement `rz`");
+        let err = parse::<StateVectorLayer>("qreg q[2];\ncx q[0], q[2];").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 10));
+        let err = parse::<StateVectorLayer>("qreg q[2];\nqreg r[3];\ncx q, r;").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 1));
+        let err = parse::<StateVectorLayer>("OPENQASM 2.0;\ninclude \"é\";").unwrap_err();
+        assert_eq!(err.message, "unsupported include \"é\"");
+        let err = parse::<StateVectorLayer>("qreg q[1];\nh é;").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+    }
+}
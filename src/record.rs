@@ -0,0 +1,405 @@
+use std::fmt;
+use std::marker::PhantomData;
+use crate::{Error, Layer, TryLayer,
+            wrapped::{TransparentWrapper, transparent_wrapper},
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{Operation, PauliOperation, HOperation, SOperation, TOperation, CXOperation,
+                         RotationOperation, U3Operation,
+                         CZOperation, SwapOperation, CCXOperation, CYOperation, ISwapOperation,
+                         ConditionalOperation, ResetOperation, OpArgs}};
+
+/// Operation which can be copied into records.
+pub trait Recordable: Sized {
+    /// Copies the operation, or returns an error if it can't be copied.
+    fn record(&self) -> Result<Self, Error>;
+}
+
+impl<T: Clone> Recordable for T {
+    fn record(&self) -> Result<Self, Error> {
+        Ok(self.clone())
+    }
+}
+
+/// `Var` can't be copied, and it is `UnsupportedOperation`.
+impl<L: Layer + ?Sized> Recordable for OpArgs<L> where L::Qubit: Clone, L::Slot: Clone {
+    fn record(&self) -> Result<Self, Error> {
+        self.try_clone().ok_or(Error::UnsupportedOperation(self.id()))
+    }
+}
+
+/// Copies operations into a record.
+fn record<T: Recordable>(ops: &[T]) -> Result<Vec<T>, Error> {
+    ops.iter().map(Recordable::record).collect()
+}
+
+/// A batch of operations sent to the layer and the measured result.
+#[derive(Debug)]
+pub struct Record<L: Layer> {
+    /// Sent operations. This is empty when only `receive` is called.
+    pub ops: Vec<L::Operation>,
+    /// Measured result. This is None when only `send` is called.
+    pub measured: Option<L::Buffer>,
+}
+
+impl<L: Layer> Clone for Record<L> where L::Operation: Clone, L::Buffer: Clone {
+    fn clone(&self) -> Self {
+        Record { ops: self.ops.clone(), measured: self.measured.clone() }
+    }
+}
+
+/// Layer which records all operations and measured results sent to the inner layer.
+///
+/// Operations of the inner layer must be `Recordable`, such as `Clone` types and `OpArgs`.
+/// Operations which can't be copied, such as `Var` of `OpArgs`, are reported as errors
+/// by `TryLayer`, and `Layer::send` panics for them.
+pub struct RecordingLayer<L: Layer> {
+    layer: L,
+    records: Vec<Record<L>>,
+}
+
+impl<L: Layer + fmt::Debug> fmt::Debug for RecordingLayer<L>
+    where L::Operation: fmt::Debug, L::Buffer: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RecordingLayer").field("layer", &self.layer).field("records", &self.records).finish()
+    }
+}
+
+impl<L: Layer> RecordingLayer<L> {
+    pub fn new(layer: L) -> Self {
+        RecordingLayer { layer, records: vec![] }
+    }
+
+    /// Gets the records in sent order.
+    pub fn records(&self) -> &[Record<L>] {
+        &self.records
+    }
+
+    /// Takes the records and clears them.
+    pub fn take_records(&mut self) -> Vec<Record<L>> {
+        std::mem::take(&mut self.records)
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.layer
+    }
+
+    pub fn into_inner(self) -> L {
+        self.layer
+    }
+
+    /// Sends recorded operations to another layer.
+    ///
+    /// Returns the measured results for records which have measured results.
+    pub fn replay<M>(&self, layer: &mut M) -> Vec<Option<M::Buffer>>
+        where M: Layer<Operation=L::Operation>
+    {
+        self.records.iter().map(|rec| send_record(layer, &rec.ops, rec.measured.is_some())).collect()
+    }
+
+    /// Sends recorded operations to another layer, with converting operations by `f`.
+    pub fn replay_with<M, F>(&self, layer: &mut M, mut f: F) -> Vec<Option<M::Buffer>>
+        where M: Layer,
+              F: FnMut(&L::Operation) -> M::Operation,
+    {
+        self.records.iter().map(|rec| {
+            let ops: Vec<_> = rec.ops.iter().map(&mut f).collect();
+            send_record(layer, &ops, rec.measured.is_some())
+        }).collect()
+    }
+}
+
+fn send_record<M: Layer>(layer: &mut M, ops: &[M::Operation], receive: bool) -> Option<M::Buffer> {
+    if receive {
+        let mut buf = layer.make_buffer();
+        layer.send_receive(ops, &mut buf);
+        Some(buf)
+    } else {
+        layer.send(ops);
+        None
+    }
+}
+
+impl<L: Layer> Layer for RecordingLayer<L>
+    where L::Operation: Recordable, L::Buffer: Clone
+{
+    type Operation = RecordingOperation<L>;
+    type Qubit = L::Qubit;
+    type Slot = L::Slot;
+    type Buffer = L::Buffer;
+    type Requested = L::Requested;
+    type Response = L::Response;
+
+    fn make_buffer(&self) -> L::Buffer {
+        self.layer.make_buffer()
+    }
+
+    fn send(&mut self, ops: &[Self::Operation]) -> L::Requested {
+        let ops = TransparentWrapper::peel_slice(ops);
+        let recorded = record(ops).unwrap_or_else(|e| panic!("{}", e));
+        self.records.push(Record { ops: recorded, measured: None });
+        self.layer.send(ops)
+    }
+
+    fn receive(&mut self, buf: &mut L::Buffer) -> L::Response {
+        let response = self.layer.receive(buf);
+        match self.records.last_mut() {
+            Some(rec) if rec.measured.is_none() => rec.measured = Some(buf.clone()),
+            _ => self.records.push(Record { ops: vec![], measured: Some(buf.clone()) }),
+        }
+        response
+    }
+
+    fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> L::Response {
+        let ops = TransparentWrapper::peel_slice(ops);
+        let recorded = record(ops).unwrap_or_else(|e| panic!("{}", e));
+        let response = self.layer.send_receive(ops, buf);
+        self.records.push(Record { ops: recorded, measured: Some(buf.clone()) });
+        response
+    }
+}
+
+/// Nothing is sent or recorded when an error is returned while recording operations.
+impl<L: TryLayer> TryLayer for RecordingLayer<L>
+    where L::Operation: Recordable, L::Buffer: Clone
+{
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<L::Requested, Error> {
+        let ops = TransparentWrapper::peel_slice(ops);
+        let recorded = record(ops)?;
+        let requested = self.layer.try_send(ops)?;
+        self.records.push(Record { ops: recorded, measured: None });
+        Ok(requested)
+    }
+
+    fn try_receive(&mut self, buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        let response = self.layer.try_receive(buf)?;
+        match self.records.last_mut() {
+            Some(rec) if rec.measured.is_none() => rec.measured = Some(buf.clone()),
+            _ => self.records.push(Record { ops: vec![], measured: Some(buf.clone()) }),
+        }
+        Ok(response)
+    }
+
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        let ops = TransparentWrapper::peel_slice(ops);
+        let recorded = record(ops)?;
+        let response = self.layer.try_send_receive(ops, buf)?;
+        self.records.push(Record { ops: recorded, measured: Some(buf.clone()) });
+        Ok(response)
+    }
+}
+
+impl<L: Layer + PauliGate> PauliGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + HGate> HGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + SGate> SGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + TGate> TGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + CXGate> CXGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + RotationGate> RotationGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + U3Gate> U3Gate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + CZGate> CZGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + SwapGate> SwapGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + CCXGate> CCXGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + CYGate> CYGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + ISwapGate> ISwapGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + ConditionalGate> ConditionalGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+impl<L: Layer + ResetGate> ResetGate for RecordingLayer<L> where L::Operation: Recordable, L::Buffer: Clone {}
+
+transparent_wrapper! {
+    /// Operation of `RecordingLayer`.
+    pub struct RecordingOperation<L>;
+}
+
+impl<L: Layer> RecordingOperation<L> {
+    pub fn new(op: L::Operation) -> Self {
+        Self(op, PhantomData)
+    }
+}
+
+impl<L: Layer> Operation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: Operation<L> + Recordable, L::Buffer: Clone
+{
+    fn initialize() -> Self {
+        Self::new(L::Operation::initialize())
+    }
+
+    fn measure(q: L::Qubit, s: L::Slot) -> Self {
+        Self::new(L::Operation::measure(q, s))
+    }
+}
+
+impl<L: Layer + PauliGate> PauliOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: PauliOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn x(q: L::Qubit) -> Self {
+        Self::new(L::Operation::x(q))
+    }
+
+    fn y(q: L::Qubit) -> Self {
+        Self::new(L::Operation::y(q))
+    }
+
+    fn z(q: L::Qubit) -> Self {
+        Self::new(L::Operation::z(q))
+    }
+}
+
+impl<L: Layer + HGate> HOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: HOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn h(q: L::Qubit) -> Self {
+        Self::new(L::Operation::h(q))
+    }
+}
+
+impl<L: Layer + SGate> SOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: SOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn s(q: L::Qubit) -> Self {
+        Self::new(L::Operation::s(q))
+    }
+
+    fn sdg(q: L::Qubit) -> Self {
+        Self::new(L::Operation::sdg(q))
+    }
+}
+
+impl<L: Layer + TGate> TOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: TOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn t(q: L::Qubit) -> Self {
+        Self::new(L::Operation::t(q))
+    }
+
+    fn tdg(q: L::Qubit) -> Self {
+        Self::new(L::Operation::tdg(q))
+    }
+}
+
+impl<L: Layer + CXGate> CXOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: CXOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn cx(c: L::Qubit, t: L::Qubit) -> Self {
+        Self::new(L::Operation::cx(c, t))
+    }
+}
+
+impl<L: Layer + RotationGate> RotationOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: RotationOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn rx(q: L::Qubit, theta: f64) -> Self {
+        Self::new(L::Operation::rx(q, theta))
+    }
+
+    fn ry(q: L::Qubit, theta: f64) -> Self {
+        Self::new(L::Operation::ry(q, theta))
+    }
+
+    fn rz(q: L::Qubit, theta: f64) -> Self {
+        Self::new(L::Operation::rz(q, theta))
+    }
+}
+
+impl<L: Layer + U3Gate> U3Operation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: U3Operation<L> + Recordable, L::Buffer: Clone
+{
+    fn u3(q: L::Qubit, theta: f64, phi: f64, lambda: f64) -> Self {
+        Self::new(L::Operation::u3(q, theta, phi, lambda))
+    }
+}
+
+impl<L: Layer + CZGate> CZOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: CZOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn cz(c: L::Qubit, t: L::Qubit) -> Self {
+        Self::new(L::Operation::cz(c, t))
+    }
+}
+
+impl<L: Layer + SwapGate> SwapOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: SwapOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn swap(a: L::Qubit, b: L::Qubit) -> Self {
+        Self::new(L::Operation::swap(a, b))
+    }
+}
+
+impl<L: Layer + CCXGate> CCXOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: CCXOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn ccx(c1: L::Qubit, c2: L::Qubit, t: L::Qubit) -> Self {
+        Self::new(L::Operation::ccx(c1, c2, t))
+    }
+}
+
+impl<L: Layer + CYGate> CYOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: CYOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn cy(c: L::Qubit, t: L::Qubit) -> Self {
+        Self::new(L::Operation::cy(c, t))
+    }
+}
+
+impl<L: Layer + ISwapGate> ISwapOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: ISwapOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn iswap(a: L::Qubit, b: L::Qubit) -> Self {
+        Self::new(L::Operation::iswap(a, b))
+    }
+}
+
+impl<L: Layer + ResetGate> ResetOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: ResetOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn reset(q: L::Qubit) -> Self {
+        Self::new(L::Operation::reset(q))
+    }
+}
+
+impl<L: Layer + ConditionalGate> ConditionalOperation<RecordingLayer<L>> for RecordingOperation<L>
+    where L::Operation: ConditionalOperation<L> + Recordable, L::Buffer: Clone
+{
+    fn if_slots(slots: Vec<L::Slot>, value: u64, op: Self) -> Self {
+        Self::new(L::Operation::if_slots(slots, value, op.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::Any;
+    use crate::{Measured, operations::opid, sim::{StateVectorLayer, StabilizerLayer}};
+
+    #[test]
+    fn record_and_replay() {
+        let mut layer = RecordingLayer::new(StateVectorLayer::new(2));
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.x(0);
+        ops.cx(0, 1);
+        layer.send(ops.as_ref());
+        let mut ops = layer.opsvec();
+        ops.measure(1, 0);
+        let mut buf = layer.make_buffer();
+        layer.send_receive(ops.as_ref(), &mut buf);
+        assert!(buf.get(0));
+
+        let records = layer.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(format!("{:?}", records[0].ops),
+                   format!("{:?}", [OpArgs::<StateVectorLayer>::Empty(opid::INIT), OpArgs::Q(opid::X, 0), OpArgs::QQ(opid::CX, 0, 1)]));
+        assert!(records[0].measured.is_none());
+        assert!(records[1].measured.as_ref().unwrap().get(0));
+
+        let mut sim = StabilizerLayer::new(2);
+        let results = layer.replay_with(&mut sim, |op| op.try_clone().unwrap().cast());
+        assert!(results[0].is_none());
+        assert_eq!(results[1], records[1].measured);
+        let mut sim = StateVectorLayer::new(2);
+        assert_eq!(layer.replay(&mut sim)[1], records[1].measured);
+
+        let var = RecordingOperation::new(OpArgs::Var(opid::USERDEF, Box::new(()) as Box<dyn Any + Send>));
+        assert!(matches!(layer.try_send(&[var]), Err(Error::UnsupportedOperation(opid::USERDEF))));
+        assert_eq!(layer.records().len(), 2);
+    }
+}
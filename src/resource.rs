@@ -0,0 +1,255 @@
+//! Layer for resource estimation.
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{Error, Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs},
+            sim::MeasuredBits};
+
+/// Resources used by operations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Resources {
+    counts: BTreeMap<u16, usize>,
+    levels: HashMap<u32, (usize, usize)>,
+    slots: HashSet<u32>,
+    /// Levels of the last measurement into each slot.
+    slot_writes: HashMap<u32, (usize, usize)>,
+    /// Levels of the last conditional operation on each slot.
+    slot_reads: HashMap<u32, (usize, usize)>,
+    depth: usize,
+    t_depth: usize,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an operation.
+    ///
+    /// Initialization is counted but doesn't affect the depth.
+    /// Conditional operations are counted both as `IF` and as the wrapped operation,
+    /// and they are placed after the measurements into their slots.
+    pub fn add<L>(&mut self, op: &OpArgs<L>)
+        where L: Layer<Qubit=u32, Slot=u32> + ?Sized
+    {
+        *self.counts.entry(op.id()).or_insert(0) += 1;
+        let mut op = op;
+        let mut conditions = vec![];
+        while let OpArgs::If(_, ref slots, _, ref inner) = *op {
+            self.slots.extend(slots);
+            conditions.extend(slots);
+            op = inner;
+            *self.counts.entry(op.id()).or_insert(0) += 1;
+        }
+        let mut measured = None;
+        let qubits = match *op {
+            OpArgs::Q(_, q) | OpArgs::QD(_, q, _) | OpArgs::QF(_, q, _) |
+            OpArgs::QFF(_, q, _, _) | OpArgs::QDDD(_, q, _, _, _) => vec![q],
+            OpArgs::QS(_, q, s) => {
+                self.slots.insert(s);
+                measured = Some(s);
+                vec![q]
+            },
+            OpArgs::QQ(_, a, b) => vec![a, b],
+            OpArgs::QQQ(_, a, b, c) => vec![a, b, c],
+            OpArgs::Empty(_) | OpArgs::Var(_, _) | OpArgs::If(..) => return,
+        };
+        let is_t = matches!(op.id(), opid::T | opid::TDG);
+        let mut after: Vec<(usize, usize)> = qubits.iter().filter_map(|q| self.levels.get(q).copied()).collect();
+        after.extend(conditions.iter().filter_map(|s| self.slot_writes.get(s).copied()));
+        if let Some(s) = measured {
+            after.extend(self.slot_writes.get(&s).copied());
+            after.extend(self.slot_reads.get(&s).copied());
+        }
+        let mut level = after.iter().map(|l| l.0).max().unwrap_or(0);
+        let mut t_level = after.iter().map(|l| l.1).max().unwrap_or(0);
+        level += 1;
+        if is_t {
+            t_level += 1;
+        }
+        for q in qubits {
+            self.levels.insert(q, (level, t_level));
+        }
+        for s in conditions {
+            let read = self.slot_reads.entry(s).or_insert((0, 0));
+            *read = (read.0.max(level), read.1.max(t_level));
+        }
+        if let Some(s) = measured {
+            self.slot_writes.insert(s, (level, t_level));
+        }
+        self.depth = self.depth.max(level);
+        self.t_depth = self.t_depth.max(t_level);
+    }
+
+    /// Gets the counts of operations by operation ID.
+    pub fn counts(&self) -> &BTreeMap<u16, usize> {
+        &self.counts
+    }
+
+    /// Gets the count of operations with given operation ID.
+    pub fn count(&self, id: u16) -> usize {
+        self.counts.get(&id).copied().unwrap_or(0)
+    }
+
+    /// Gets the total number of operations.
+    pub fn n_ops(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Gets the circuit depth, including measurements.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets the number of T and T† gates.
+    ///
+    /// Other gates such as Toffoli are not expanded. Put `DecomposeLayer` over
+    /// `ResourceCounterLayer` to count them in T gates.
+    pub fn t_count(&self) -> usize {
+        self.count(opid::T) + self.count(opid::TDG)
+    }
+
+    /// Gets the number of layers of T and T† gates on the longest path.
+    pub fn t_depth(&self) -> usize {
+        self.t_depth
+    }
+
+    /// Gets the number of CNOT gates.
+    pub fn cx_count(&self) -> usize {
+        self.count(opid::CX)
+    }
+
+    /// Gets the number of qubits touched.
+    pub fn n_qubits(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Gets the number of slots touched.
+    pub fn n_slots(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+/// Layer which counts resources of sent operations without executing them.
+///
+/// Resources are accumulated over sends. Measured results are always false.
+#[derive(Debug, Default)]
+pub struct ResourceCounterLayer {
+    resources: Resources,
+}
+
+impl ResourceCounterLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Takes the resources and clears them.
+    pub fn take_resources(&mut self) -> Resources {
+        std::mem::take(&mut self.resources)
+    }
+}
+
+impl Layer for ResourceCounterLayer {
+    type Operation = OpArgs<Self>;
+    type Qubit = u32;
+    type Slot = u32;
+    type Buffer = MeasuredBits;
+    type Requested = ();
+    type Response = ();
+
+    fn send(&mut self, ops: &[OpArgs<Self>]) {
+        for op in ops {
+            self.resources.add(op);
+        }
+    }
+
+    fn receive(&mut self, buf: &mut MeasuredBits) {
+        buf.clear();
+    }
+
+    fn make_buffer(&self) -> MeasuredBits {
+        MeasuredBits::new()
+    }
+}
+
+/// Every operation is accepted.
+impl TryLayer for ResourceCounterLayer {
+    fn try_send(&mut self, ops: &[OpArgs<Self>]) -> Result<(), Error> {
+        self.send(ops);
+        Ok(())
+    }
+
+    fn try_receive(&mut self, buf: &mut MeasuredBits) -> Result<(), Error> {
+        self.receive(buf);
+        Ok(())
+    }
+}
+
+impl PauliGate for ResourceCounterLayer {}
+impl HGate for ResourceCounterLayer {}
+impl SGate for ResourceCounterLayer {}
+impl TGate for ResourceCounterLayer {}
+impl CXGate for ResourceCounterLayer {}
+impl RotationGate for ResourceCounterLayer {}
+impl U3Gate for ResourceCounterLayer {}
+impl CZGate for ResourceCounterLayer {}
+impl SwapGate for ResourceCounterLayer {}
+impl CCXGate for ResourceCounterLayer {}
+impl CYGate for ResourceCounterLayer {}
+impl ISwapGate for ResourceCounterLayer {}
+impl ConditionalGate for ResourceCounterLayer {}
+impl ResetGate for ResourceCounterLayer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count() {
+        let mut layer = ResourceCounterLayer::new();
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.t(0);
+        ops.t(1);
+        ops.cx(0, 1);
+        ops.tdg(1);
+        ops.h(2);
+        ops.ccx(0, 1, 2);
+        ops.measure(2, 5);
+        layer.send(ops.as_ref());
+        layer.send(ops.as_ref());
+
+        let r = layer.resources();
+        assert_eq!(r.n_ops(), 18);
+        assert_eq!(r.count(opid::H), 4);
+        assert_eq!(r.t_count(), 6);
+        assert_eq!(r.cx_count(), 2);
+        assert_eq!(r.count(opid::CCX), 2);
+        assert_eq!(r.depth(), 11);
+        assert_eq!(r.t_depth(), 4);
+        assert_eq!((r.n_qubits(), r.n_slots()), (3, 1));
+    }
+
+    #[test]
+    fn conditional() {
+        let mut layer = ResourceCounterLayer::new();
+        let mut ops = layer.opsvec();
+        ops.h(1);
+        ops.measure(1, 0);
+        ops.if_slot(0, true, |ops| ops.t(0));
+        ops.measure(2, 0);
+        layer.send(ops.as_ref());
+
+        let r = layer.resources();
+        assert_eq!(r.count(opid::IF), 1);
+        assert_eq!(r.depth(), 4);
+        assert_eq!(r.t_depth(), 1);
+    }
+}
@@ -0,0 +1,318 @@
+//! Layer which routes two-qubit operations on the device coupling graph.
+use std::collections::VecDeque;
+
+use crate::{Error, Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs, CXOperation}};
+
+/// Undirected graph of physical qubits which can interact directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CouplingMap {
+    adj: Vec<Vec<u32>>,
+}
+
+impl CouplingMap {
+    /// Makes a graph with no edges.
+    pub fn new(n_qubits: u32) -> Self {
+        CouplingMap { adj: vec![vec![]; n_qubits as usize] }
+    }
+
+    /// Makes a graph from given edges.
+    pub fn from_edges<I: IntoIterator<Item=(u32, u32)>>(n_qubits: u32, edges: I) -> Self {
+        let mut map = Self::new(n_qubits);
+        for (a, b) in edges {
+            map.add_edge(a, b);
+        }
+        map
+    }
+
+    /// Makes a graph where qubit `i` and `i + 1` are connected.
+    pub fn line(n_qubits: u32) -> Self {
+        Self::from_edges(n_qubits, (1..n_qubits).map(|i| (i - 1, i)))
+    }
+
+    /// Connects qubit `a` and `b`.
+    pub fn add_edge(&mut self, a: u32, b: u32) -> &mut Self {
+        assert!(a != b, "Self loop is not allowed.");
+        assert!(a < self.n_qubits() && b < self.n_qubits(), "Qubit is out of range.");
+        if !self.is_adjacent(a, b) {
+            self.adj[a as usize].push(b);
+            self.adj[b as usize].push(a);
+        }
+        self
+    }
+
+    pub fn n_qubits(&self) -> u32 {
+        self.adj.len() as u32
+    }
+
+    pub fn is_adjacent(&self, a: u32, b: u32) -> bool {
+        self.adj[a as usize].contains(&b)
+    }
+
+    /// Finds a shortest path from `a` to `b`, including both ends.
+    pub fn shortest_path(&self, a: u32, b: u32) -> Option<Vec<u32>> {
+        let mut prev = vec![None; self.adj.len()];
+        let mut queue = VecDeque::new();
+        prev[a as usize] = Some(a);
+        queue.push_back(a);
+        while let Some(p) = queue.pop_front() {
+            if p == b {
+                let mut path = vec![b];
+                let mut p = b;
+                while p != a {
+                    p = prev[p as usize].unwrap();
+                    path.push(p);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &n in &self.adj[p as usize] {
+                if prev[n as usize].is_none() {
+                    prev[n as usize] = Some(p);
+                    queue.push_back(n);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Layer which makes two-qubit operations nearest-neighbour by inserting SWAPs.
+///
+/// Qubits of this layer are logical qubits. When a two-qubit operation is applied
+/// to qubits which are not adjacent on the coupling graph, the control is moved
+/// along a shortest path by SWAPs, which are built from three CX gates.
+/// The logical-to-physical layout is tracked over sends, so later operations and
+/// measurements refer to logical qubits. `initialize` restores the initial layout.
+/// Three-qubit operations are not routed, so they have to be decomposed beforehand.
+#[derive(Debug)]
+pub struct RoutingLayer<L> {
+    layer: L,
+    coupling: CouplingMap,
+    initial: Vec<u32>,
+    layout: Vec<u32>,
+    n_swaps: usize,
+}
+
+impl<L> RoutingLayer<L>
+    where L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate,
+          L::Slot: Clone,
+{
+    /// Makes a layer where logical qubit `i` is initially placed on physical qubit `i`.
+    pub fn new(layer: L, coupling: CouplingMap) -> Self {
+        let layout = (0..coupling.n_qubits()).collect();
+        Self::with_layout(layer, coupling, layout)
+    }
+
+    /// Makes a layer where logical qubit `i` is initially placed on physical qubit `layout[i]`.
+    pub fn with_layout(layer: L, coupling: CouplingMap, layout: Vec<u32>) -> Self {
+        let mut used = vec![false; coupling.n_qubits() as usize];
+        assert_eq!(layout.len(), used.len(), "Layout length is not equal to the number of qubits.");
+        for &p in &layout {
+            assert!(!std::mem::replace(&mut used[p as usize], true), "Layout is not a permutation.");
+        }
+        RoutingLayer { layer, coupling, initial: layout.clone(), layout, n_swaps: 0 }
+    }
+
+    pub fn coupling(&self) -> &CouplingMap {
+        &self.coupling
+    }
+
+    /// Gets current layout. Logical qubit `i` is placed on physical qubit `layout()[i]`.
+    pub fn layout(&self) -> &[u32] {
+        &self.layout
+    }
+
+    /// Gets the number of inserted SWAPs.
+    pub fn n_swaps(&self) -> usize {
+        self.n_swaps
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.layer
+    }
+
+    pub fn into_inner(self) -> L {
+        self.layer
+    }
+
+    fn physical(layout: &[u32], q: u32) -> Result<u32, Error> {
+        layout.get(q as usize).copied().ok_or_else(|| Error::QubitOutOfRange(q.to_string()))
+    }
+
+    /// Rewrites operations for the inner layer, with the layout after them.
+    fn route(&self, ops: &[OpArgs<Self>]) -> Result<Routed<L>, Error> {
+        let mut layout = self.layout.clone();
+        let mut routed = Vec::with_capacity(ops.len());
+        let mut n_swaps = 0;
+        for op in ops {
+            // SWAPs for conditional gates are inserted unconditionally, so the layout is always known.
+            let (mut op, mut conds) = (op, vec![]);
+            while let OpArgs::If(id, slots, value, inner) = op {
+                conds.push((*id, slots, *value));
+                op = inner;
+            }
+            if op.id() == opid::INIT {
+                if !conds.is_empty() {
+                    return Err(Error::InvalidOperation("conditional initialize can't be routed".to_string()));
+                }
+                layout.copy_from_slice(&self.initial);
+            }
+            let mut gate = if let OpArgs::QQ(id, c, t) = *op {
+                let (pc, pt) = (Self::physical(&layout, c)?, Self::physical(&layout, t)?);
+                if pc == pt {
+                    return Err(Error::InvalidOperation(format!("same qubits {} and {}", c, t)));
+                }
+                let path = self.coupling.shortest_path(pc, pt).ok_or_else(|| {
+                    Error::InvalidOperation(format!("qubits {} and {} are not connected", c, t))
+                })?;
+                for w in path[..path.len() - 1].windows(2) {
+                    let (a, b) = (w[0], w[1]);
+                    routed.push(OpArgs::cx(a, b));
+                    routed.push(OpArgs::cx(b, a));
+                    routed.push(OpArgs::cx(a, b));
+                    for p in layout.iter_mut() {
+                        if *p == a {
+                            *p = b;
+                        } else if *p == b {
+                            *p = a;
+                        }
+                    }
+                    n_swaps += 1;
+                }
+                OpArgs::QQ(id, path[path.len() - 2], pt)
+            } else if let OpArgs::QQQ(id, ..) = *op {
+                return Err(Error::UnsupportedOperation(id));
+            } else {
+                op.try_clone().ok_or(Error::UnsupportedOperation(op.id()))?.try_map(|q| Self::physical(&layout, q), Ok)?
+            };
+            for (id, slots, value) in conds.into_iter().rev() {
+                gate = OpArgs::If(id, slots.clone(), value, Box::new(gate));
+            }
+            routed.push(gate);
+        }
+        Ok(Routed { ops: routed, layout, n_swaps })
+    }
+
+    fn commit(&mut self, routed: Routed<L>) {
+        self.layout = routed.layout;
+        self.n_swaps += routed.n_swaps;
+    }
+}
+
+struct Routed<L: Layer> {
+    ops: Vec<OpArgs<L>>,
+    layout: Vec<u32>,
+    n_swaps: usize,
+}
+
+impl<L> Layer for RoutingLayer<L>
+    where L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate,
+          L::Slot: Clone,
+{
+    type Operation = OpArgs<Self>;
+    type Qubit = u32;
+    type Slot = L::Slot;
+    type Buffer = L::Buffer;
+    type Requested = L::Requested;
+    type Response = L::Response;
+
+    fn make_buffer(&self) -> L::Buffer {
+        self.layer.make_buffer()
+    }
+
+    fn send(&mut self, ops: &[Self::Operation]) -> L::Requested {
+        let mut routed = self.route(ops).unwrap_or_else(|e| panic!("{}", e));
+        let ops = std::mem::take(&mut routed.ops);
+        self.commit(routed);
+        self.layer.send(&ops)
+    }
+
+    fn receive(&mut self, buf: &mut L::Buffer) -> L::Response {
+        self.layer.receive(buf)
+    }
+
+    fn send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> L::Response {
+        let mut routed = self.route(ops).unwrap_or_else(|e| panic!("{}", e));
+        let ops = std::mem::take(&mut routed.ops);
+        self.commit(routed);
+        self.layer.send_receive(&ops, buf)
+    }
+}
+
+impl<L> TryLayer for RoutingLayer<L>
+    where L: TryLayer<Operation=OpArgs<L>, Qubit=u32> + CXGate,
+          L::Slot: Clone,
+{
+    /// Sends operations. Nothing is sent and the layout is not changed when an error is returned.
+    fn try_send(&mut self, ops: &[Self::Operation]) -> Result<L::Requested, Error> {
+        let routed = self.route(ops)?;
+        let requested = self.layer.try_send(&routed.ops)?;
+        self.commit(routed);
+        Ok(requested)
+    }
+
+    fn try_receive(&mut self, buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        self.layer.try_receive(buf)
+    }
+
+    fn try_send_receive(&mut self, ops: &[Self::Operation], buf: &mut L::Buffer) -> Result<L::Response, Error> {
+        let routed = self.route(ops)?;
+        let response = self.layer.try_send_receive(&routed.ops, buf)?;
+        self.commit(routed);
+        Ok(response)
+    }
+}
+
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + PauliGate> PauliGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + HGate> HGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + SGate> SGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + TGate> TGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate> CXGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + RotationGate> RotationGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + U3Gate> U3Gate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + CZGate> CZGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + SwapGate> SwapGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + CYGate> CYGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + ISwapGate> ISwapGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + ConditionalGate> ConditionalGate for RoutingLayer<L> where L::Slot: Clone {}
+impl<L: Layer<Operation=OpArgs<L>, Qubit=u32> + CXGate + ResetGate> ResetGate for RoutingLayer<L> where L::Slot: Clone {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Measured, sim::StateVectorLayer};
+
+    #[test]
+    fn route_on_line() {
+        let mut layer = RoutingLayer::new(StateVectorLayer::new(4), CouplingMap::line(4));
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        ops.x(0);
+        ops.cx(0, 3);
+        ops.cx(3, 1);
+        for q in 0..4 {
+            ops.measure(q, q);
+        }
+        let mut buf = layer.make_buffer();
+        layer.send_receive(ops.as_ref(), &mut buf);
+        assert_eq!((0..4).map(|s| buf.get(s)).collect::<Vec<_>>(), vec![true, true, false, true]);
+        assert_eq!(layer.layout(), &[3, 0, 2, 1]);
+        assert_eq!(layer.n_swaps(), 4);
+
+        let mut ops = layer.opsvec();
+        ops.initialize();
+        assert!(layer.try_send(ops.as_ref()).is_ok());
+        assert_eq!(layer.layout(), &[0, 1, 2, 3]);
+
+        let mut layer = RoutingLayer::new(StateVectorLayer::new(3), CouplingMap::from_edges(3, vec![(0, 1)]));
+        let mut ops = layer.opsvec();
+        ops.cx(0, 2);
+        assert!(matches!(layer.try_send(ops.as_ref()), Err(Error::InvalidOperation(_))));
+        ops.clear();
+        ops.x(5);
+        assert!(matches!(layer.try_send(ops.as_ref()), Err(Error::QubitOutOfRange(_))));
+    }
+}
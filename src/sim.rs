@@ -0,0 +1,140 @@
+//! Reference simulators.
+//!
+//! Simulators in this module take `OpArgs` as their `Operation` and
+//! execute operations immediately when they are sent.
+
+use crate::{Error, Layer, operations::{opid, OpArgs}};
+
+/// Upper bound of slots in simulators, which keeps `MeasuredBits` small.
+/// Operations with a slot `MAX_SLOTS` or above are `SlotOutOfRange`.
+pub const MAX_SLOTS: u32 = 1 << 20;
+
+/// Implements `TryLayer` for a simulator which has `n_qubits` and `apply` methods.
+/// `$supported` tells whether a gate is supported, as `check_op` takes.
+macro_rules! impl_try_layer {
+    ($sim:ty, $supported:expr) => {
+        /// Operations are checked before execution, so nothing is executed when an error is returned.
+        impl $crate::TryLayer for $sim {
+            fn try_send(&mut self, ops: &[$crate::operations::OpArgs<Self>]) -> Result<(), $crate::Error> {
+                for op in ops {
+                    $crate::sim::check_op(op, self.n_qubits(), $supported)?;
+                }
+                for op in ops {
+                    self.apply(op);
+                }
+                Ok(())
+            }
+
+            fn try_receive(&mut self, buf: &mut $crate::sim::MeasuredBits) -> Result<(), $crate::Error> {
+                $crate::Layer::receive(self, buf);
+                Ok(())
+            }
+        }
+    }
+}
+
+pub(crate) mod rng;
+mod matrix;
+
+mod statevec;
+pub use statevec::StateVectorLayer;
+
+mod bits;
+pub use bits::MeasuredBits;
+
+mod stabilizer;
+pub use stabilizer::StabilizerLayer;
+
+mod noise;
+pub use noise::{Channel, NoiseModel};
+
+mod densitymatrix;
+pub use densitymatrix::DensityMatrixLayer;
+
+mod linalg;
+mod mps;
+pub use mps::MpsLayer;
+
+/// Checks qubits, slots and the operation ID before executing the operation.
+/// `supported` tells whether the gate is supported.
+pub(crate) fn check_op<L>(op: &OpArgs<L>, n_qubits: u32, supported: impl Fn(&OpArgs<L>) -> bool) -> Result<(), Error>
+    where L: Layer<Qubit=u32, Slot=u32> + ?Sized
+{
+    let check_qubits = |qs: &[u32]| {
+        for (i, &q) in qs.iter().enumerate() {
+            if q >= n_qubits {
+                return Err(Error::QubitOutOfRange(q.to_string()));
+            }
+            if qs[..i].contains(&q) {
+                return Err(Error::InvalidOperation(format!("qubit {} is used more than once", q)));
+            }
+        }
+        Ok(())
+    };
+    let check_slots = |ss: &[u32]| match ss.iter().find(|&&s| s >= MAX_SLOTS) {
+        Some(s) => Err(Error::SlotOutOfRange(s.to_string())),
+        None => Ok(()),
+    };
+    match *op {
+        OpArgs::Empty(opid::INIT) => Ok(()),
+        OpArgs::QS(opid::MEAS, q, s) => check_slots(&[s]).and_then(|_| check_qubits(&[q])),
+        OpArgs::Q(opid::RESET, q) => check_qubits(&[q]),
+        OpArgs::If(_, ref slots, value, ref op) => {
+            check_slots(slots)?;
+            if slots.len() > 64 || (slots.len() < 64 && value >> slots.len() != 0) {
+                return Err(Error::InvalidOperation(format!("value {} doesn't fit in {} slots", value, slots.len())));
+            }
+            // A block of `if_slots` is applied op by op, so a measurement into a condition slot,
+            // or initialize which clears all slots, would change the condition for the rest of the block.
+            let mut inner = &**op;
+            while let OpArgs::If(_, _, _, ref op) = *inner {
+                inner = op;
+            }
+            match *inner {
+                OpArgs::QS(opid::MEAS, _, s) if slots.contains(&s) =>
+                    return Err(Error::InvalidOperation(format!("slot {} is measured under a condition on it", s))),
+                OpArgs::Empty(opid::INIT) =>
+                    return Err(Error::InvalidOperation("initialize under a condition".to_string())),
+                _ => {},
+            }
+            check_op(op, n_qubits, supported)
+        },
+        _ if !supported(op) => Err(Error::UnsupportedOperation(op.id())),
+        OpArgs::QD(_, _, theta) | OpArgs::QDDD(_, _, theta, _, _) |
+        OpArgs::QDDD(_, _, _, theta, _) | OpArgs::QDDD(_, _, _, _, theta) if !theta.is_finite() =>
+            Err(Error::InvalidOperation(format!("angle {} is not finite", theta))),
+        OpArgs::Q(_, q) | OpArgs::QD(_, q, _) | OpArgs::QDDD(_, q, _, _, _) => check_qubits(&[q]),
+        OpArgs::QQ(_, a, b) => check_qubits(&[a, b]),
+        OpArgs::QQQ(_, a, b, c) => check_qubits(&[a, b, c]),
+        _ => Err(Error::UnsupportedOperation(op.id())),
+    }
+}
+
+/// Returns true if the operation is a builtin two- or three-qubit gate.
+pub(crate) fn is_multi<L: Layer + ?Sized>(op: &OpArgs<L>) -> bool {
+    matches!(op, OpArgs::QQ(opid::CX | opid::CZ | opid::SWAP | opid::CY | opid::ISWAP, _, _) |
+                 OpArgs::QQQ(opid::CCX, _, _, _))
+}
+
+/// Decomposes builtin two- and three-qubit gates other than CX into CX and single qubit gates.
+///
+/// Only H, S, S† and CX are used except for CCX, so Clifford gates stay Clifford.
+pub(crate) fn decompose<L>(op: &OpArgs<L>) -> Vec<OpArgs<L>>
+    where L: Layer<Qubit=u32, Slot=u32> + ?Sized
+{
+    use OpArgs::{Q, QQ};
+    match *op {
+        QQ(opid::CZ, c, t) => vec![Q(opid::H, t), QQ(opid::CX, c, t), Q(opid::H, t)],
+        QQ(opid::CY, c, t) => vec![Q(opid::SDG, t), QQ(opid::CX, c, t), Q(opid::S, t)],
+        QQ(opid::SWAP, a, b) => vec![QQ(opid::CX, a, b), QQ(opid::CX, b, a), QQ(opid::CX, a, b)],
+        QQ(opid::ISWAP, a, b) => vec![Q(opid::S, a), Q(opid::S, b), Q(opid::H, a),
+                                      QQ(opid::CX, a, b), QQ(opid::CX, b, a), Q(opid::H, b)],
+        OpArgs::QQQ(opid::CCX, a, b, c) => vec![
+            Q(opid::H, c), QQ(opid::CX, b, c), Q(opid::TDG, c), QQ(opid::CX, a, c),
+            Q(opid::T, c), QQ(opid::CX, b, c), Q(opid::TDG, c), QQ(opid::CX, a, c),
+            Q(opid::T, b), Q(opid::T, c), Q(opid::H, c), QQ(opid::CX, a, b),
+            Q(opid::T, a), Q(opid::TDG, b), QQ(opid::CX, a, b),
+        ],
+        _ => op.try_clone().into_iter().collect(),
+    }
+}
@@ -0,0 +1,44 @@
+use crate::Measured;
+
+/// Buffer of measured bits used by simulators.
+///
+/// Slots which are never measured are read as `false`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MeasuredBits(Vec<bool>);
+
+impl MeasuredBits {
+    pub fn new() -> Self {
+        MeasuredBits(vec![])
+    }
+
+    /// Sets a measured result.
+    pub fn set(&mut self, n: u32, value: bool) {
+        let n = n as usize;
+        if self.0.len() <= n {
+            self.0.resize(n + 1, false);
+        }
+        self.0[n] = value;
+    }
+
+    /// Clears all measured results.
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    /// Returns true if slot `slots[i]` holds bit `i` of `value` for every `i`.
+    pub fn holds(&self, slots: &[u32], value: u64) -> bool {
+        slots.iter().enumerate().all(|(i, &s)| self.get(s) == (value >> i & 1 == 1))
+    }
+
+    pub fn as_slice(&self) -> &[bool] {
+        &self.0
+    }
+}
+
+impl Measured for MeasuredBits {
+    type Slot = u32;
+
+    fn get(&self, n: u32) -> bool {
+        self.0.get(n as usize).copied().unwrap_or(false)
+    }
+}
@@ -0,0 +1,306 @@
+use num_complex::Complex64;
+
+use crate::{Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs}};
+use crate::sim::{is_multi, decompose, MeasuredBits, NoiseModel, rng::Rng, matrix::{self, Matrix2}};
+
+/// Density matrix simulator with noise channels.
+///
+/// Holds 4^n complex values, so this is suitable for small number of qubits.
+/// Noises are configured by `NoiseModel`.
+#[derive(Debug)]
+pub struct DensityMatrixLayer {
+    n_qubits: u32,
+    dim: usize,
+    /// Row-major 2^n x 2^n matrix.
+    rho: Vec<Complex64>,
+    noise: NoiseModel,
+    bits: MeasuredBits,
+    rng: Rng,
+}
+
+impl DensityMatrixLayer {
+    /// Makes a noiseless simulator with seed 0.
+    pub fn new(n_qubits: u32) -> Self {
+        Self::with_seed(n_qubits, 0)
+    }
+
+    /// Makes a noiseless simulator with given seed for measurement.
+    pub fn with_seed(n_qubits: u32, seed: u64) -> Self {
+        assert!(2 * n_qubits < usize::BITS, "Too many qubits.");
+        let dim = 1 << n_qubits;
+        let mut rho = vec![Complex64::new(0.0, 0.0); dim * dim];
+        rho[0] = Complex64::new(1.0, 0.0);
+        DensityMatrixLayer {
+            n_qubits, dim, rho, noise: NoiseModel::new(), bits: MeasuredBits::new(), rng: Rng::new(seed)
+        }
+    }
+
+    pub fn n_qubits(&self) -> u32 {
+        self.n_qubits
+    }
+
+    /// Gets the density matrix in row-major order.
+    /// Qubit `q` corresponds to bit `q` of row and column indices.
+    pub fn density_matrix(&self) -> &[Complex64] {
+        &self.rho
+    }
+
+    pub fn noise_model(&self) -> &NoiseModel {
+        &self.noise
+    }
+
+    pub fn noise_model_mut(&mut self) -> &mut NoiseModel {
+        &mut self.noise
+    }
+
+    pub fn set_noise_model(&mut self, noise: NoiseModel) {
+        self.noise = noise;
+    }
+
+    fn initialize(&mut self) {
+        for a in self.rho.iter_mut() {
+            *a = Complex64::new(0.0, 0.0);
+        }
+        self.rho[0] = Complex64::new(1.0, 0.0);
+        self.bits.clear();
+    }
+
+    /// Calculates `m rho m†` on qubit `q`.
+    fn conjugate(rho: &mut [Complex64], dim: usize, m: &Matrix2, q: u32) {
+        let mask = 1usize << q;
+        // rho <- m rho
+        for r in (0..dim).filter(|r| r & mask == 0) {
+            for c in 0..dim {
+                let (a0, a1) = (rho[r * dim + c], rho[(r | mask) * dim + c]);
+                rho[r * dim + c] = m[0][0] * a0 + m[0][1] * a1;
+                rho[(r | mask) * dim + c] = m[1][0] * a0 + m[1][1] * a1;
+            }
+        }
+        // rho <- rho m†
+        for r in 0..dim {
+            for c in (0..dim).filter(|c| c & mask == 0) {
+                let (a0, a1) = (rho[r * dim + c], rho[r * dim + (c | mask)]);
+                rho[r * dim + c] = a0 * m[0][0].conj() + a1 * m[0][1].conj();
+                rho[r * dim + (c | mask)] = a0 * m[1][0].conj() + a1 * m[1][1].conj();
+            }
+        }
+    }
+
+    fn apply_kraus(&mut self, kraus: &[Matrix2], q: u32) {
+        let mut sum = vec![Complex64::new(0.0, 0.0); self.rho.len()];
+        for k in kraus {
+            let mut term = self.rho.clone();
+            Self::conjugate(&mut term, self.dim, k, q);
+            for (s, t) in sum.iter_mut().zip(term) {
+                *s += t;
+            }
+        }
+        self.rho = sum;
+    }
+
+    fn apply_noise(&mut self, id: u16, q: u32) {
+        let kraus: Vec<_> = self.noise.channels(id, q).map(|ch| ch.kraus()).collect();
+        for k in kraus {
+            self.apply_kraus(&k, q);
+        }
+    }
+
+    /// Resets qubit `q` to |0> by the channel with Kraus operators |0><0| and |0><1|.
+    fn reset(&mut self, q: u32) {
+        let (zero, one) = (Complex64::new(0.0, 0.0), Complex64::new(1.0, 0.0));
+        self.apply_kraus(&[[[one, zero], [zero, zero]], [[zero, one], [zero, zero]]], q);
+        self.apply_noise(opid::RESET, q);
+    }
+
+    fn apply_single(&mut self, m: &Matrix2, q: u32) {
+        Self::conjugate(&mut self.rho, self.dim, m, q);
+    }
+
+    fn apply_cx(&mut self, c: u32, t: u32) {
+        let (cmask, tmask) = (1usize << c, 1usize << t);
+        let perm = |i: usize| if i & cmask != 0 { i ^ tmask } else { i };
+        let dim = self.dim;
+        let old = self.rho.clone();
+        for r in 0..dim {
+            for col in 0..dim {
+                self.rho[perm(r) * dim + perm(col)] = old[r * dim + col];
+            }
+        }
+    }
+
+    fn measure(&mut self, q: u32, s: u32) {
+        let (dim, mask) = (self.dim, 1usize << q);
+        let p1: f64 = (0..dim).filter(|i| i & mask != 0).map(|i| self.rho[i * dim + i].re).sum();
+        let result = self.rng.next_f64() < p1;
+        let norm = if result { p1 } else { 1.0 - p1 };
+        for r in 0..dim {
+            for c in 0..dim {
+                let a = &mut self.rho[r * dim + c];
+                if (r & mask != 0) == result && (c & mask != 0) == result {
+                    *a /= norm;
+                } else {
+                    *a = Complex64::new(0.0, 0.0);
+                }
+            }
+        }
+        let flip = self.rng.next_f64() < self.noise.readout_error(q);
+        self.bits.set(s, result ^ flip);
+    }
+
+    /// Applies the unitary of the gate without noise.
+    fn apply_gate(&mut self, op: &OpArgs<Self>) {
+        match op {
+            OpArgs::QQ(opid::CX, c, t) => self.apply_cx(*c, *t),
+            OpArgs::QQ(..) | OpArgs::QQQ(..) => {
+                for op in decompose(op) {
+                    self.apply_gate(&op);
+                }
+            },
+            OpArgs::Q(_, q) | OpArgs::QD(_, q, _) | OpArgs::QDDD(_, q, _, _, _) => {
+                let m = matrix::gate(op).unwrap();
+                self.apply_single(&m, *q);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn apply(&mut self, op: &OpArgs<Self>) {
+        match op {
+            OpArgs::Empty(opid::INIT) => self.initialize(),
+            OpArgs::QS(opid::MEAS, q, s) => self.measure(*q, *s),
+            OpArgs::Q(opid::RESET, q) => self.reset(*q),
+            OpArgs::If(_, slots, value, op) => if self.bits.holds(slots, *value) {
+                self.apply(op);
+            },
+            // Multi-qubit gates are decomposed without noise, and the noise of the original gate
+            // is applied once on each qubit.
+            OpArgs::Q(id, q) | OpArgs::QD(id, q, _) | OpArgs::QDDD(id, q, _, _, _) => {
+                self.apply_gate(op);
+                self.apply_noise(*id, *q);
+            },
+            OpArgs::QQ(id, a, b) => {
+                self.apply_gate(op);
+                self.apply_noise(*id, *a);
+                self.apply_noise(*id, *b);
+            },
+            OpArgs::QQQ(id, a, b, c) => {
+                self.apply_gate(op);
+                self.apply_noise(*id, *a);
+                self.apply_noise(*id, *b);
+                self.apply_noise(*id, *c);
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Layer for DensityMatrixLayer {
+    type Operation = OpArgs<Self>;
+    type Qubit = u32;
+    type Slot = u32;
+    type Buffer = MeasuredBits;
+    type Requested = ();
+    type Response = ();
+
+    fn send(&mut self, ops: &[OpArgs<Self>]) {
+        self.try_send(ops).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn receive(&mut self, buf: &mut MeasuredBits) {
+        buf.clone_from(&self.bits);
+    }
+
+    fn make_buffer(&self) -> MeasuredBits {
+        MeasuredBits::new()
+    }
+}
+
+impl_try_layer!(DensityMatrixLayer, |op| matrix::gate(op).is_some() || is_multi(op));
+
+impl PauliGate for DensityMatrixLayer {}
+impl HGate for DensityMatrixLayer {}
+impl SGate for DensityMatrixLayer {}
+impl TGate for DensityMatrixLayer {}
+impl CXGate for DensityMatrixLayer {}
+impl RotationGate for DensityMatrixLayer {}
+impl U3Gate for DensityMatrixLayer {}
+impl CZGate for DensityMatrixLayer {}
+impl SwapGate for DensityMatrixLayer {}
+impl CCXGate for DensityMatrixLayer {}
+impl CYGate for DensityMatrixLayer {}
+impl ISwapGate for DensityMatrixLayer {}
+impl ConditionalGate for DensityMatrixLayer {}
+impl ResetGate for DensityMatrixLayer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Channel;
+
+    #[test]
+    fn amplitude_damping() {
+        let mut sim = DensityMatrixLayer::new(2);
+        sim.noise_model_mut().add_qubit_noise(1, Channel::AmplitudeDamping(0.25));
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.x(0);
+        ops.x(1);
+        sim.send(ops.as_ref());
+        let rho = sim.density_matrix();
+        assert!((rho[15].re - 0.75).abs() < 1e-12);
+        assert!((rho[5].re - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reset() {
+        let mut sim = DensityMatrixLayer::new(2);
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.cx(0, 1);
+        ops.reset(0);
+        sim.send(ops.as_ref());
+        // Qubit 0 is |0> and qubit 1 is maximally mixed.
+        let rho = sim.density_matrix();
+        for (i, a) in rho.iter().enumerate() {
+            let expected = if i == 0 || i == 2 * 4 + 2 { 0.5 } else { 0.0 };
+            assert!((a - expected).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn depolarizing_bell() {
+        let mut sim = DensityMatrixLayer::new(2);
+        sim.noise_model_mut().add_gate_noise(opid::CX, Channel::Depolarizing(0.3));
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.cx(0, 1);
+        sim.send(ops.as_ref());
+        let rho = sim.density_matrix();
+        let trace: f64 = (0..4).map(|i| rho[i * 4 + i].re).sum();
+        assert!((trace - 1.0).abs() < 1e-12);
+        // Coherence between |00> and |11> decays.
+        assert!(rho[3].re < 0.5 && rho[3].re > 0.0);
+    }
+
+    #[test]
+    fn multi_qubit_gate_noise() {
+        // Depolarizing noise of CZ, which flips |0> with probability 1/4, is applied once on each qubit, and H and CX in its decomposition are noiseless.
+        let mut sim = DensityMatrixLayer::new(2);
+        sim.noise_model_mut().add_gate_noise(opid::CZ, Channel::Depolarizing(0.375));
+        sim.noise_model_mut().add_gate_noise(opid::H, Channel::Depolarizing(0.75));
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.cz(0, 1);
+        sim.send(ops.as_ref());
+        let rho = sim.density_matrix();
+        let expected = [0.75 * 0.75, 0.25 * 0.75, 0.75 * 0.25, 0.25 * 0.25];
+        for (i, p) in expected.iter().enumerate() {
+            assert!((rho[i * 4 + i].re - p).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use num_complex::Complex64;
+
+/// Result of singular value decomposition `a = u diag(s) vh`.
+///
+/// For `m x n` matrix `a` and `k = min(m, n)`, `u` is `m x k`, `vh` is `k x n`
+/// and `s` is sorted in descending order. Matrices are row-major.
+#[derive(Debug)]
+pub(crate) struct Svd {
+    pub u: Vec<Complex64>,
+    pub s: Vec<f64>,
+    pub vh: Vec<Complex64>,
+}
+
+/// Computes the thin SVD of `m x n` row-major matrix with one-sided Jacobi method.
+pub(crate) fn svd(a: &[Complex64], m: usize, n: usize) -> Svd {
+    assert_eq!(a.len(), m * n);
+    if m < n {
+        // a† = u' s v'†, so a = v' s u'†.
+        let Svd { u, s, vh } = svd(&adjoint(a, m, n), n, m);
+        return Svd { u: adjoint(&vh, m, m), s, vh: adjoint(&u, n, m) };
+    }
+    // Work on columns: col[j][i] = a[i][j].
+    let mut cols: Vec<Vec<Complex64>> = (0..n).map(|j| (0..m).map(|i| a[i * n + j]).collect()).collect();
+    let mut v: Vec<Vec<Complex64>> = (0..n).map(|j| {
+        let mut e = vec![Complex64::new(0.0, 0.0); n];
+        e[j] = Complex64::new(1.0, 0.0);
+        e
+    }).collect();
+    for _ in 0..100 {
+        let mut rotated = false;
+        for p in 0..n {
+            for q in p + 1..n {
+                let alpha: f64 = cols[p].iter().map(|x| x.norm_sqr()).sum();
+                let beta: f64 = cols[q].iter().map(|x| x.norm_sqr()).sum();
+                let gamma: Complex64 = cols[p].iter().zip(&cols[q]).map(|(x, y)| x.conj() * y).sum();
+                let g = gamma.norm();
+                if g <= 1e-15 * (alpha * beta).sqrt() || g == 0.0 {
+                    continue;
+                }
+                rotated = true;
+                let zeta = (beta - alpha) / (2.0 * g);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+                let phase = (gamma / g).conj();
+                for vecs in [&mut cols, &mut v] {
+                    let (left, right) = vecs.split_at_mut(q);
+                    for (x, y) in left[p].iter_mut().zip(right[0].iter_mut()) {
+                        let y2 = *y * phase;
+                        let x2 = *x;
+                        *x = x2 * c - y2 * s;
+                        *y = x2 * s + y2 * c;
+                    }
+                }
+            }
+        }
+        if !rotated {
+            break;
+        }
+    }
+    let mut order: Vec<(f64, usize)> = cols.iter()
+                                           .map(|c| c.iter().map(|x| x.norm_sqr()).sum::<f64>().sqrt())
+                                           .zip(0..n)
+                                           .collect();
+    order.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    let mut u = vec![Complex64::new(0.0, 0.0); m * n];
+    let mut vh = vec![Complex64::new(0.0, 0.0); n * n];
+    let mut s = Vec::with_capacity(n);
+    for (k, &(sigma, j)) in order.iter().enumerate() {
+        s.push(sigma);
+        if sigma > 0.0 {
+            for i in 0..m {
+                u[i * n + k] = cols[j][i] / sigma;
+            }
+        }
+        for i in 0..n {
+            vh[k * n + i] = v[j][i].conj();
+        }
+    }
+    Svd { u, s, vh }
+}
+
+/// Conjugate transpose of `m x n` row-major matrix.
+fn adjoint(a: &[Complex64], m: usize, n: usize) -> Vec<Complex64> {
+    let mut b = vec![Complex64::new(0.0, 0.0); m * n];
+    for i in 0..m {
+        for j in 0..n {
+            b[j * m + i] = a[i * n + j].conj();
+        }
+    }
+    b
+}
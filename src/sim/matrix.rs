@@ -0,0 +1,60 @@
+use std::f64::consts::FRAC_1_SQRT_2;
+use num_complex::Complex64;
+
+use crate::{Layer, operations::{opid, OpArgs}};
+
+/// 2x2 matrix in row-major order.
+pub(crate) type Matrix2 = [[Complex64; 2]; 2];
+
+const fn c(re: f64, im: f64) -> Complex64 {
+    Complex64::new(re, im)
+}
+
+const ZERO: Complex64 = c(0.0, 0.0);
+const ONE: Complex64 = c(1.0, 0.0);
+
+/// Returns the matrix of a builtin single qubit gate.
+pub(crate) fn single(id: u16) -> Option<Matrix2> {
+    let m = match id {
+        opid::X => [[ZERO, ONE], [ONE, ZERO]],
+        opid::Y => [[ZERO, c(0.0, -1.0)], [c(0.0, 1.0), ZERO]],
+        opid::Z => [[ONE, ZERO], [ZERO, c(-1.0, 0.0)]],
+        opid::H => [[c(FRAC_1_SQRT_2, 0.0), c(FRAC_1_SQRT_2, 0.0)],
+                    [c(FRAC_1_SQRT_2, 0.0), c(-FRAC_1_SQRT_2, 0.0)]],
+        opid::S => [[ONE, ZERO], [ZERO, c(0.0, 1.0)]],
+        opid::SDG => [[ONE, ZERO], [ZERO, c(0.0, -1.0)]],
+        opid::T => [[ONE, ZERO], [ZERO, c(FRAC_1_SQRT_2, FRAC_1_SQRT_2)]],
+        opid::TDG => [[ONE, ZERO], [ZERO, c(FRAC_1_SQRT_2, -FRAC_1_SQRT_2)]],
+        _ => return None,
+    };
+    Some(m)
+}
+
+/// Returns the matrix of a builtin single qubit gate, including parametric gates.
+pub(crate) fn gate<L: Layer + ?Sized>(op: &OpArgs<L>) -> Option<Matrix2> {
+    match *op {
+        OpArgs::Q(id, _) => single(id),
+        OpArgs::QD(id, _, theta) => rotation(id, theta),
+        OpArgs::QDDD(opid::U3, _, theta, phi, lambda) => Some(u3(theta, phi, lambda)),
+        _ => None,
+    }
+}
+
+/// Returns the matrix of a rotation gate.
+pub(crate) fn rotation(id: u16, theta: f64) -> Option<Matrix2> {
+    let (cos, sin) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+    let m = match id {
+        opid::RX => [[c(cos, 0.0), c(0.0, -sin)], [c(0.0, -sin), c(cos, 0.0)]],
+        opid::RY => [[c(cos, 0.0), c(-sin, 0.0)], [c(sin, 0.0), c(cos, 0.0)]],
+        opid::RZ => [[c(cos, -sin), ZERO], [ZERO, c(cos, sin)]],
+        _ => return None,
+    };
+    Some(m)
+}
+
+/// Returns the matrix of U3(θ, φ, λ).
+pub(crate) fn u3(theta: f64, phi: f64, lambda: f64) -> Matrix2 {
+    let (cos, sin) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+    [[c(cos, 0.0), -Complex64::from_polar(sin, lambda)],
+     [Complex64::from_polar(sin, phi), Complex64::from_polar(cos, phi + lambda)]]
+}
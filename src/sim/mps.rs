@@ -0,0 +1,409 @@
+use num_complex::Complex64;
+
+use crate::{Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs}};
+use crate::sim::{is_multi, decompose, MeasuredBits, rng::Rng, matrix::{self, Matrix2}, linalg::{svd, Svd}};
+
+/// 4x4 matrix in row-major order. Basis index is `2 * s_left + s_right`.
+type Matrix4 = [[Complex64; 4]; 4];
+
+/// Site tensor with shape (left bond, physical, right bond).
+#[derive(Debug, Clone)]
+struct Site {
+    left: usize,
+    right: usize,
+    data: Vec<Complex64>,
+}
+
+impl Site {
+    fn zero() -> Self {
+        Site { left: 1, right: 1, data: vec![Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)] }
+    }
+
+    fn idx(&self, l: usize, s: usize, r: usize) -> usize {
+        (l * 2 + s) * self.right + r
+    }
+}
+
+/// Matrix product state simulator.
+///
+/// Qubits are arranged on a 1D chain. Bond dimensions are bounded by
+/// `max_bond_dim` and singular values smaller than `cutoff` times the largest one
+/// are discarded, so low-entanglement circuits on many qubits can be simulated.
+/// CNOT on non-adjacent qubits is applied with SWAPs internally.
+///
+/// The state is kept in mixed canonical form.
+#[derive(Debug)]
+pub struct MpsLayer {
+    sites: Vec<Site>,
+    /// Orthogonality center.
+    center: usize,
+    max_bond_dim: usize,
+    cutoff: f64,
+    /// Sum of discarded squared singular values.
+    truncation_error: f64,
+    bits: MeasuredBits,
+    rng: Rng,
+}
+
+impl MpsLayer {
+    /// Makes a simulator with seed 0.
+    pub fn new(n_qubits: u32) -> Self {
+        Self::with_seed(n_qubits, 0)
+    }
+
+    /// Makes a simulator with given seed for measurement.
+    pub fn with_seed(n_qubits: u32, seed: u64) -> Self {
+        assert!(n_qubits > 0, "At least one qubit is required.");
+        MpsLayer {
+            sites: vec![Site::zero(); n_qubits as usize],
+            center: 0,
+            max_bond_dim: usize::MAX,
+            cutoff: 1e-12,
+            truncation_error: 0.0,
+            bits: MeasuredBits::new(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn n_qubits(&self) -> u32 {
+        self.sites.len() as u32
+    }
+
+    /// Sets the upper bound of bond dimensions.
+    pub fn set_max_bond_dim(&mut self, max_bond_dim: usize) {
+        assert!(max_bond_dim > 0, "Bond dimension must be positive.");
+        self.max_bond_dim = max_bond_dim;
+    }
+
+    /// Sets the threshold of singular values, relative to the largest one, to be discarded.
+    pub fn set_cutoff(&mut self, cutoff: f64) {
+        self.cutoff = cutoff;
+    }
+
+    /// Gets the current bond dimensions between adjacent qubits.
+    pub fn bond_dims(&self) -> Vec<usize> {
+        self.sites[..self.sites.len() - 1].iter().map(|s| s.right).collect()
+    }
+
+    /// Gets the sum of squared singular values discarded by truncation since initialization.
+    pub fn truncation_error(&self) -> f64 {
+        self.truncation_error
+    }
+
+    fn initialize(&mut self) {
+        for s in self.sites.iter_mut() {
+            *s = Site::zero();
+        }
+        self.center = 0;
+        self.truncation_error = 0.0;
+        self.bits.clear();
+    }
+
+    fn apply_single(&mut self, m: &Matrix2, q: u32) {
+        let site = &mut self.sites[q as usize];
+        for l in 0..site.left {
+            for r in 0..site.right {
+                let (i0, i1) = (site.idx(l, 0, r), site.idx(l, 1, r));
+                let (a0, a1) = (site.data[i0], site.data[i1]);
+                site.data[i0] = m[0][0] * a0 + m[0][1] * a1;
+                site.data[i1] = m[1][0] * a0 + m[1][1] * a1;
+            }
+        }
+    }
+
+    /// Splits `theta` with shape (left * 2, 2 * right) into two sites.
+    /// When `truncate` is false, only zero singular values are discarded.
+    fn split(&mut self, theta: &[Complex64], left: usize, right: usize, truncate: bool) -> (Vec<Complex64>, Vec<f64>, Vec<Complex64>, usize) {
+        let (m, n) = (left * 2, 2 * right);
+        let Svd { u, s, vh } = svd(theta, m, n);
+        let k_all = s.len();
+        let mut k = s.iter().take_while(|&&x| x > 1e-15 * s[0]).count().max(1);
+        if truncate {
+            k = s.iter().take_while(|&&x| x >= self.cutoff * s[0]).count().clamp(1, self.max_bond_dim).min(k);
+        }
+        let kept: f64 = s[..k].iter().map(|x| x * x).sum();
+        let total: f64 = s.iter().map(|x| x * x).sum();
+        self.truncation_error += total - kept;
+        // Keep the state normalized.
+        let scale = (total / kept).sqrt();
+        let s = s[..k].iter().map(|x| x * scale).collect();
+        let u = (0..m).flat_map(|i| u[i * k_all..i * k_all + k].iter().copied()).collect();
+        (u, s, vh[..k * n].to_vec(), k)
+    }
+
+    /// Moves the orthogonality center to `q`.
+    fn move_center(&mut self, q: usize) {
+        while self.center < q {
+            let i = self.center;
+            let theta = self.contract(i);
+            let (left, right) = (self.sites[i].left, self.sites[i + 1].right);
+            let (u, s, vh, k) = self.split(&theta, left, right, false);
+            self.set_pair(i, u, s, vh, k, false);
+            self.center += 1;
+        }
+        while self.center > q {
+            let i = self.center - 1;
+            let theta = self.contract(i);
+            let (left, right) = (self.sites[i].left, self.sites[i + 1].right);
+            let (u, s, vh, k) = self.split(&theta, left, right, false);
+            self.set_pair(i, u, s, vh, k, true);
+            self.center -= 1;
+        }
+    }
+
+    /// Contracts sites `i` and `i + 1` into a matrix with shape (left * 2, 2 * right).
+    fn contract(&self, i: usize) -> Vec<Complex64> {
+        let (a, b) = (&self.sites[i], &self.sites[i + 1]);
+        let mut theta = vec![Complex64::new(0.0, 0.0); a.left * 4 * b.right];
+        for l in 0..a.left {
+            for s1 in 0..2 {
+                for m in 0..a.right {
+                    let x = a.data[a.idx(l, s1, m)];
+                    if x == Complex64::new(0.0, 0.0) {
+                        continue;
+                    }
+                    for s2 in 0..2 {
+                        for r in 0..b.right {
+                            theta[((l * 2 + s1) * 2 + s2) * b.right + r] += x * b.data[b.idx(m, s2, r)];
+                        }
+                    }
+                }
+            }
+        }
+        theta
+    }
+
+    /// Stores the decomposed pair. Singular values are absorbed into the left site
+    /// when `to_left` is true, otherwise into the right site.
+    fn set_pair(&mut self, i: usize, mut u: Vec<Complex64>, s: Vec<f64>, mut vh: Vec<Complex64>, k: usize, to_left: bool) {
+        let (left, right) = (self.sites[i].left, self.sites[i + 1].right);
+        if to_left {
+            for (j, x) in u.iter_mut().enumerate() {
+                *x *= s[j % k];
+            }
+        } else {
+            for (j, x) in vh.iter_mut().enumerate() {
+                *x *= s[j / (2 * right)];
+            }
+        }
+        self.sites[i] = Site { left, right: k, data: u };
+        self.sites[i + 1] = Site { left: k, right, data: vh };
+    }
+
+    /// Applies a gate on sites `i` and `i + 1`.
+    fn apply_adjacent(&mut self, g: &Matrix4, i: usize) {
+        self.move_center(i);
+        let theta = self.contract(i);
+        let (left, right) = (self.sites[i].left, self.sites[i + 1].right);
+        let mut applied = vec![Complex64::new(0.0, 0.0); theta.len()];
+        for l in 0..left {
+            for r in 0..right {
+                let idx = |s: usize| ((l * 2 + s / 2) * 2 + s % 2) * right + r;
+                for (row, g_row) in g.iter().enumerate() {
+                    applied[idx(row)] = (0..4).map(|col| g_row[col] * theta[idx(col)]).sum();
+                }
+            }
+        }
+        let (u, s, vh, k) = self.split(&applied, left, right, true);
+        self.set_pair(i, u, s, vh, k, false);
+        self.center = i + 1;
+    }
+
+    fn apply_cx(&mut self, c: u32, t: u32) {
+        let one = Complex64::new(1.0, 0.0);
+        let zero = Complex64::new(0.0, 0.0);
+        let mut swap = [[zero; 4]; 4];
+        let mut cx_left = [[zero; 4]; 4];
+        let mut cx_right = [[zero; 4]; 4];
+        for (i, j) in [(0, 0), (1, 2), (2, 1), (3, 3)] {
+            swap[i][j] = one;
+        }
+        for (i, j) in [(0, 0), (1, 1), (2, 3), (3, 2)] {
+            cx_left[i][j] = one;
+        }
+        for (i, j) in [(0, 0), (1, 3), (2, 2), (3, 1)] {
+            cx_right[i][j] = one;
+        }
+        // Moves the control next to the target, then moves it back.
+        let (c, t) = (c as usize, t as usize);
+        if c < t {
+            for i in c..t - 1 {
+                self.apply_adjacent(&swap, i);
+            }
+            self.apply_adjacent(&cx_left, t - 1);
+            for i in (c..t - 1).rev() {
+                self.apply_adjacent(&swap, i);
+            }
+        } else {
+            for i in (t + 1..c).rev() {
+                self.apply_adjacent(&swap, i);
+            }
+            self.apply_adjacent(&cx_right, t);
+            for i in t + 1..c {
+                self.apply_adjacent(&swap, i);
+            }
+        }
+    }
+
+    /// Projects qubit `q` to a random outcome and returns the outcome.
+    fn collapse(&mut self, q: u32) -> bool {
+        let q = q as usize;
+        self.move_center(q);
+        let site = &mut self.sites[q];
+        let mut p1 = 0.0;
+        for l in 0..site.left {
+            for r in 0..site.right {
+                p1 += site.data[site.idx(l, 1, r)].norm_sqr();
+            }
+        }
+        let result = self.rng.next_f64() < p1;
+        let norm = if result { p1 } else { 1.0 - p1 }.sqrt();
+        for l in 0..site.left {
+            for r in 0..site.right {
+                let (keep, drop) = if result { (1, 0) } else { (0, 1) };
+                let (ik, id) = (site.idx(l, keep, r), site.idx(l, drop, r));
+                site.data[ik] /= norm;
+                site.data[id] = Complex64::new(0.0, 0.0);
+            }
+        }
+        result
+    }
+
+    fn measure(&mut self, q: u32, s: u32) {
+        let result = self.collapse(q);
+        self.bits.set(s, result);
+    }
+
+    fn reset(&mut self, q: u32) {
+        if self.collapse(q) {
+            self.apply_single(&matrix::single(opid::X).unwrap(), q);
+        }
+    }
+
+    fn apply(&mut self, op: &OpArgs<Self>) {
+        match op {
+            OpArgs::Empty(opid::INIT) => self.initialize(),
+            OpArgs::QS(opid::MEAS, q, s) => self.measure(*q, *s),
+            OpArgs::Q(opid::RESET, q) => self.reset(*q),
+            OpArgs::If(_, slots, value, op) => if self.bits.holds(slots, *value) {
+                self.apply(op);
+            },
+            OpArgs::Q(_, q) | OpArgs::QD(_, q, _) | OpArgs::QDDD(_, q, _, _, _) => {
+                let m = matrix::gate(op).unwrap();
+                self.apply_single(&m, *q);
+            },
+            OpArgs::QQ(opid::CX, c, t) => self.apply_cx(*c, *t),
+            OpArgs::QQ(..) | OpArgs::QQQ(..) => {
+                for op in decompose(op) {
+                    self.apply(&op);
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Layer for MpsLayer {
+    type Operation = OpArgs<Self>;
+    type Qubit = u32;
+    type Slot = u32;
+    type Buffer = MeasuredBits;
+    type Requested = ();
+    type Response = ();
+
+    fn send(&mut self, ops: &[OpArgs<Self>]) {
+        self.try_send(ops).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn receive(&mut self, buf: &mut MeasuredBits) {
+        buf.clone_from(&self.bits);
+    }
+
+    fn make_buffer(&self) -> MeasuredBits {
+        MeasuredBits::new()
+    }
+}
+
+impl_try_layer!(MpsLayer, |op| matrix::gate(op).is_some() || is_multi(op));
+
+impl PauliGate for MpsLayer {}
+impl HGate for MpsLayer {}
+impl SGate for MpsLayer {}
+impl TGate for MpsLayer {}
+impl CXGate for MpsLayer {}
+impl RotationGate for MpsLayer {}
+impl U3Gate for MpsLayer {}
+impl CZGate for MpsLayer {}
+impl SwapGate for MpsLayer {}
+impl CCXGate for MpsLayer {}
+impl CYGate for MpsLayer {}
+impl ISwapGate for MpsLayer {}
+impl ConditionalGate for MpsLayer {}
+impl ResetGate for MpsLayer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Measured;
+
+    #[test]
+    fn long_ghz() {
+        let n = 120;
+        for seed in 0..2 {
+            let mut sim = MpsLayer::with_seed(n, seed);
+            sim.set_max_bond_dim(4);
+            let mut ops = sim.opsvec();
+            ops.initialize();
+            ops.h(0);
+            for q in 1..n {
+                ops.cx(q - 1, q);
+            }
+            for q in (0..n).rev() {
+                ops.measure(q, q);
+            }
+            let mut buf = sim.make_buffer();
+            sim.send_receive(ops.as_ref(), &mut buf);
+            assert!((0..n).all(|q| buf.get(q) == buf.get(0)));
+            assert!(sim.truncation_error() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn matches_state_vector() {
+        use crate::sim::StateVectorLayer;
+        let n = 5;
+        let mut mps = MpsLayer::new(n);
+        let mut sv = StateVectorLayer::new(n);
+        let mut a = mps.opsvec();
+        let mut b = sv.opsvec();
+        for (i, j) in [(0, 3), (4, 1), (2, 0), (1, 2), (3, 4)] {
+            a.h(i);
+            b.h(i);
+            a.t(j);
+            b.t(j);
+            a.cx(i, j);
+            b.cx(i, j);
+            a.s(j);
+            b.s(j);
+        }
+        mps.send(a.as_ref());
+        sv.send(b.as_ref());
+        // Compares the probabilities of each qubit.
+        for q in 0..n as usize {
+            mps.move_center(q);
+            let site = &mps.sites[q];
+            let p_mps: f64 = (0..site.left).flat_map(|l| (0..site.right).map(move |r| (l, r)))
+                                           .map(|(l, r)| site.data[site.idx(l, 1, r)].norm_sqr())
+                                           .sum();
+            let p_sv: f64 = sv.state().iter()
+                                      .enumerate()
+                                      .filter(|(i, _)| i >> q & 1 == 1)
+                                      .map(|(_, a)| a.norm_sqr())
+                                      .sum();
+            assert!((p_mps - p_sv).abs() < 1e-10, "{} {}", p_mps, p_sv);
+        }
+    }
+}
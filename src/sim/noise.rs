@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use num_complex::Complex64;
+
+use crate::sim::matrix::{self, Matrix2};
+use crate::operations::opid;
+
+/// Single qubit noise channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    /// Applies X, Y or Z with probability p / 3 each.
+    Depolarizing(f64),
+    /// Decays |1> to |0> with probability gamma.
+    AmplitudeDamping(f64),
+    /// Applies Z with probability p.
+    Dephasing(f64),
+}
+
+impl Channel {
+    fn probability(&self) -> f64 {
+        match *self {
+            Channel::Depolarizing(p) | Channel::AmplitudeDamping(p) | Channel::Dephasing(p) => p,
+        }
+    }
+
+    /// Gets Kraus operators of the channel.
+    pub(crate) fn kraus(&self) -> Vec<Matrix2> {
+        let scale = |m: Matrix2, k: f64| {
+            let mut m = m;
+            for v in m.iter_mut().flat_map(|r| r.iter_mut()) {
+                *v *= k;
+            }
+            m
+        };
+        let id = [[Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)],
+                  [Complex64::new(0.0, 0.0), Complex64::new(1.0, 0.0)]];
+        let pauli = |id| matrix::single(id).unwrap();
+        match *self {
+            Channel::Depolarizing(p) => vec![
+                scale(id, (1.0 - p).sqrt()),
+                scale(pauli(opid::X), (p / 3.0).sqrt()),
+                scale(pauli(opid::Y), (p / 3.0).sqrt()),
+                scale(pauli(opid::Z), (p / 3.0).sqrt()),
+            ],
+            Channel::AmplitudeDamping(gamma) => {
+                let zero = Complex64::new(0.0, 0.0);
+                vec![
+                    [[Complex64::new(1.0, 0.0), zero], [zero, Complex64::new((1.0 - gamma).sqrt(), 0.0)]],
+                    [[zero, Complex64::new(gamma.sqrt(), 0.0)], [zero, zero]],
+                ]
+            },
+            Channel::Dephasing(p) => vec![
+                scale(id, (1.0 - p).sqrt()),
+                scale(pauli(opid::Z), p.sqrt()),
+            ],
+        }
+    }
+}
+
+/// Noise configuration for `DensityMatrixLayer`.
+///
+/// After each gate and reset, channels registered for its operation ID and
+/// channels registered for each qubit it acts on are applied to those qubits.
+/// Initialize and measurement are noiseless, and measurement only has the readout error.
+/// Probabilities must be in `0.0..=1.0`.
+#[derive(Debug, Clone, Default)]
+pub struct NoiseModel {
+    gate: HashMap<u16, Vec<Channel>>,
+    qubit: HashMap<u32, Vec<Channel>>,
+    readout: HashMap<u32, f64>,
+}
+
+impl NoiseModel {
+    /// Makes a noiseless model.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a channel applied after every operation with the operation ID.
+    pub fn add_gate_noise(&mut self, id: u16, ch: Channel) -> &mut Self {
+        check_probability(ch.probability());
+        self.gate.entry(id).or_default().push(ch);
+        self
+    }
+
+    /// Adds a channel applied after every operation which acts on the qubit.
+    pub fn add_qubit_noise(&mut self, q: u32, ch: Channel) -> &mut Self {
+        check_probability(ch.probability());
+        self.qubit.entry(q).or_default().push(ch);
+        self
+    }
+
+    /// Sets the probability that measured result of the qubit is flipped.
+    pub fn set_readout_error(&mut self, q: u32, p: f64) -> &mut Self {
+        check_probability(p);
+        self.readout.insert(q, p);
+        self
+    }
+
+    pub(crate) fn channels<'a>(&'a self, id: u16, q: u32) -> impl Iterator<Item=&'a Channel> + 'a {
+        self.gate.get(&id).into_iter().flatten().chain(self.qubit.get(&q).into_iter().flatten())
+    }
+
+    pub(crate) fn readout_error(&self, q: u32) -> f64 {
+        self.readout.get(&q).copied().unwrap_or(0.0)
+    }
+}
+
+fn check_probability(p: f64) {
+    assert!((0.0..=1.0).contains(&p), "Invalid probability {}.", p);
+}
@@ -0,0 +1,312 @@
+use crate::{Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, CXGate, CZGate, SwapGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs}};
+use crate::sim::{is_multi, decompose, MeasuredBits, rng::Rng};
+
+/// Stabilizer simulator for Clifford circuits.
+///
+/// This is an implementation of CHP tableau algorithm by Aaronson and Gottesman.
+/// Memory usage is O(n^2) bits and each gate takes O(n) time,
+/// so thousands of qubits can be simulated.
+///
+/// T gate, Toffoli gate and rotation gates are not Clifford gates, so this layer
+/// does not implement `TGate`, `CCXGate`, `RotationGate` nor `U3Gate`.
+#[derive(Debug)]
+pub struct StabilizerLayer {
+    n_qubits: u32,
+    /// Number of u64 words in a row.
+    words: usize,
+    /// X bits of 2n + 1 rows. Rows 0..n are destabilizers, n..2n are stabilizers
+    /// and 2n is a scratch row.
+    xs: Vec<u64>,
+    /// Z bits of 2n + 1 rows.
+    zs: Vec<u64>,
+    /// Signs of 2n + 1 rows.
+    rs: Vec<bool>,
+    bits: MeasuredBits,
+    rng: Rng,
+}
+
+impl StabilizerLayer {
+    /// Makes a simulator with seed 0.
+    pub fn new(n_qubits: u32) -> Self {
+        Self::with_seed(n_qubits, 0)
+    }
+
+    /// Makes a simulator with given seed for measurement.
+    pub fn with_seed(n_qubits: u32, seed: u64) -> Self {
+        let n = n_qubits as usize;
+        let words = n.div_ceil(64);
+        let mut layer = StabilizerLayer {
+            n_qubits,
+            words,
+            xs: vec![0; (2 * n + 1) * words],
+            zs: vec![0; (2 * n + 1) * words],
+            rs: vec![false; 2 * n + 1],
+            bits: MeasuredBits::new(),
+            rng: Rng::new(seed),
+        };
+        layer.initialize();
+        layer
+    }
+
+    pub fn n_qubits(&self) -> u32 {
+        self.n_qubits
+    }
+
+    fn n_rows(&self) -> usize {
+        self.rs.len()
+    }
+
+    fn bit(&self, v: &[u64], row: usize, q: u32) -> bool {
+        v[row * self.words + (q / 64) as usize] >> (q % 64) & 1 == 1
+    }
+
+    fn initialize(&mut self) {
+        let n = self.n_qubits as usize;
+        for w in self.xs.iter_mut().chain(self.zs.iter_mut()) {
+            *w = 0;
+        }
+        for r in self.rs.iter_mut() {
+            *r = false;
+        }
+        for i in 0..n {
+            self.xs[i * self.words + i / 64] |= 1 << (i % 64);
+            self.zs[(i + n) * self.words + i / 64] |= 1 << (i % 64);
+        }
+        self.bits.clear();
+    }
+
+    /// Applies `f(x, z, r) -> (x, z, r)` to column `q` of all rows.
+    fn update_column(&mut self, q: u32, f: impl Fn(bool, bool, bool) -> (bool, bool, bool)) {
+        let (w, mask) = ((q / 64) as usize, 1u64 << (q % 64));
+        for row in 0..self.n_rows() {
+            let i = row * self.words + w;
+            let (x, z, r) = f(self.xs[i] & mask != 0, self.zs[i] & mask != 0, self.rs[row]);
+            self.xs[i] = if x { self.xs[i] | mask } else { self.xs[i] & !mask };
+            self.zs[i] = if z { self.zs[i] | mask } else { self.zs[i] & !mask };
+            self.rs[row] = r;
+        }
+    }
+
+    fn apply_cx(&mut self, c: u32, t: u32) {
+        for row in 0..self.n_rows() {
+            let (xc, zc) = (self.bit(&self.xs, row, c), self.bit(&self.zs, row, c));
+            let (xt, zt) = (self.bit(&self.xs, row, t), self.bit(&self.zs, row, t));
+            self.rs[row] ^= xc && zt && !(xt ^ zc);
+            let i = row * self.words;
+            if xc {
+                self.xs[i + (t / 64) as usize] ^= 1 << (t % 64);
+            }
+            if zt {
+                self.zs[i + (c / 64) as usize] ^= 1 << (c % 64);
+            }
+        }
+    }
+
+    /// Multiplies row `i` into row `h`.
+    fn rowsum(&mut self, h: usize, i: usize) {
+        let (hw, iw) = (h * self.words, i * self.words);
+        // Sum of the phase exponents of i, mod 4.
+        let mut phase = 2 * (self.rs[h] as i64 + self.rs[i] as i64);
+        for k in 0..self.words {
+            let (x1, z1) = (self.xs[iw + k], self.zs[iw + k]);
+            let (x2, z2) = (self.xs[hw + k], self.zs[hw + k]);
+            let (y1, xonly1, zonly1) = (x1 & z1, x1 & !z1, !x1 & z1);
+            let pos = (y1 & z2 & !x2) | (xonly1 & z2 & x2) | (zonly1 & x2 & !z2);
+            let neg = (y1 & x2 & !z2) | (xonly1 & z2 & !x2) | (zonly1 & x2 & z2);
+            phase += pos.count_ones() as i64 - neg.count_ones() as i64;
+            self.xs[hw + k] ^= x1;
+            self.zs[hw + k] ^= z1;
+        }
+        self.rs[h] = phase.rem_euclid(4) == 2;
+    }
+
+    fn copy_row(&mut self, dst: usize, src: usize) {
+        let w = self.words;
+        self.xs.copy_within(src * w..(src + 1) * w, dst * w);
+        self.zs.copy_within(src * w..(src + 1) * w, dst * w);
+        self.rs[dst] = self.rs[src];
+    }
+
+    fn clear_row(&mut self, row: usize) {
+        let w = self.words;
+        for k in row * w..(row + 1) * w {
+            self.xs[k] = 0;
+            self.zs[k] = 0;
+        }
+        self.rs[row] = false;
+    }
+
+    /// Measures qubit `q` and returns the outcome.
+    fn collapse(&mut self, q: u32) -> bool {
+        let n = self.n_qubits as usize;
+        let p = (n..2 * n).find(|&p| self.bit(&self.xs, p, q));
+        if let Some(p) = p {
+            // Outcome is random.
+            for i in 0..2 * n {
+                if i != p && self.bit(&self.xs, i, q) {
+                    self.rowsum(i, p);
+                }
+            }
+            self.copy_row(p - n, p);
+            self.clear_row(p);
+            self.zs[p * self.words + (q / 64) as usize] |= 1 << (q % 64);
+            let result = self.rng.next_u64() & 1 == 1;
+            self.rs[p] = result;
+            result
+        } else {
+            // Outcome is determined.
+            let scratch = 2 * n;
+            self.clear_row(scratch);
+            for i in 0..n {
+                if self.bit(&self.xs, i, q) {
+                    self.rowsum(scratch, i + n);
+                }
+            }
+            self.rs[scratch]
+        }
+    }
+
+    fn measure(&mut self, q: u32, s: u32) {
+        let result = self.collapse(q);
+        self.bits.set(s, result);
+    }
+
+    fn reset(&mut self, q: u32) {
+        if self.collapse(q) {
+            self.update_column(q, |x, z, r| (x, z, r ^ z));
+        }
+    }
+
+    fn apply(&mut self, op: &OpArgs<Self>) {
+        match op {
+            OpArgs::Empty(opid::INIT) => self.initialize(),
+            OpArgs::QS(opid::MEAS, q, s) => self.measure(*q, *s),
+            OpArgs::Q(opid::RESET, q) => self.reset(*q),
+            OpArgs::If(_, slots, value, op) => if self.bits.holds(slots, *value) {
+                self.apply(op);
+            },
+            OpArgs::Q(opid::X, q) => self.update_column(*q, |x, z, r| (x, z, r ^ z)),
+            OpArgs::Q(opid::Y, q) => self.update_column(*q, |x, z, r| (x, z, r ^ x ^ z)),
+            OpArgs::Q(opid::Z, q) => self.update_column(*q, |x, z, r| (x, z, r ^ x)),
+            OpArgs::Q(opid::H, q) => self.update_column(*q, |x, z, r| (z, x, r ^ (x && z))),
+            OpArgs::Q(opid::S, q) => self.update_column(*q, |x, z, r| (x, z ^ x, r ^ (x && z))),
+            OpArgs::Q(opid::SDG, q) => self.update_column(*q, |x, z, r| (x, z ^ x, r ^ (x && !z))),
+            OpArgs::QQ(opid::CX, c, t) => self.apply_cx(*c, *t),
+            OpArgs::QQ(..) => {
+                for op in decompose(op) {
+                    self.apply(&op);
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Layer for StabilizerLayer {
+    type Operation = OpArgs<Self>;
+    type Qubit = u32;
+    type Slot = u32;
+    type Buffer = MeasuredBits;
+    type Requested = ();
+    type Response = ();
+
+    fn send(&mut self, ops: &[OpArgs<Self>]) {
+        self.try_send(ops).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn receive(&mut self, buf: &mut MeasuredBits) {
+        buf.clone_from(&self.bits);
+    }
+
+    fn make_buffer(&self) -> MeasuredBits {
+        MeasuredBits::new()
+    }
+}
+
+impl_try_layer!(StabilizerLayer, |op| {
+    matches!(op, OpArgs::Q(opid::X | opid::Y | opid::Z | opid::H | opid::S | opid::SDG, _)) ||
+    (is_multi(op) && op.id() != opid::CCX)
+});
+
+impl PauliGate for StabilizerLayer {}
+impl HGate for StabilizerLayer {}
+impl SGate for StabilizerLayer {}
+impl CXGate for StabilizerLayer {}
+impl CZGate for StabilizerLayer {}
+impl SwapGate for StabilizerLayer {}
+impl CYGate for StabilizerLayer {}
+impl ISwapGate for StabilizerLayer {}
+impl ConditionalGate for StabilizerLayer {}
+impl ResetGate for StabilizerLayer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Measured;
+
+    #[test]
+    fn ghz() {
+        let n = 200;
+        let mut sim = StabilizerLayer::with_seed(n, 1);
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.h(0);
+        for q in 1..n {
+            ops.cx(q - 1, q);
+        }
+        for q in 0..n {
+            ops.measure(q, q);
+        }
+        let mut buf = sim.make_buffer();
+        sim.send_receive(ops.as_ref(), &mut buf);
+        assert!((0..n).all(|q| buf.get(q) == buf.get(0)));
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut sim = StabilizerLayer::new(3);
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.s(0);
+        ops.s(0);
+        ops.h(0);
+        ops.y(1);
+        ops.h(2);
+        ops.sdg(2);
+        ops.s(2);
+        ops.h(2);
+        for q in 0..3 {
+            ops.measure(q, q);
+        }
+        let mut buf = sim.make_buffer();
+        sim.send_receive(ops.as_ref(), &mut buf);
+        assert_eq!(buf.get_range_u8(0, 3), 0b011);
+    }
+
+    #[test]
+    fn ancilla_reuse() {
+        for seed in 0..10 {
+            let mut sim = StabilizerLayer::with_seed(3, seed);
+            let mut ops = sim.opsvec();
+            ops.initialize();
+            ops.h(0);
+            ops.cx(0, 1);
+            ops.x(1);
+            for round in 0..4 {
+                ops.reset(2);
+                ops.cx(0, 2);
+                ops.cx(1, 2);
+                ops.measure(2, round);
+            }
+            ops.measure(0, 4);
+            ops.measure(1, 5);
+            let mut buf = sim.make_buffer();
+            sim.send_receive(ops.as_ref(), &mut buf);
+            assert_eq!(buf.get_range_u8(0, 4), 0b1111);
+            assert_ne!(buf.get(4), buf.get(5));
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use num_complex::Complex64;
+
+use crate::{Layer, TryLayer,
+            gates::{PauliGate, HGate, SGate, TGate, CXGate, RotationGate, U3Gate,
+                    CZGate, SwapGate, CCXGate, CYGate, ISwapGate, ConditionalGate, ResetGate},
+            operations::{opid, OpArgs}};
+use crate::sim::{is_multi, decompose, MeasuredBits, rng::Rng, matrix::{self, Matrix2}};
+
+/// State vector simulator.
+///
+/// Holds 2^n complex amplitudes, so this is suitable for small number of qubits.
+#[derive(Debug)]
+pub struct StateVectorLayer {
+    n_qubits: u32,
+    state: Vec<Complex64>,
+    bits: MeasuredBits,
+    rng: Rng,
+}
+
+impl StateVectorLayer {
+    /// Makes a simulator with seed 0.
+    pub fn new(n_qubits: u32) -> Self {
+        Self::with_seed(n_qubits, 0)
+    }
+
+    /// Makes a simulator with given seed for measurement.
+    pub fn with_seed(n_qubits: u32, seed: u64) -> Self {
+        assert!(n_qubits < usize::BITS, "Too many qubits.");
+        let mut state = vec![Complex64::new(0.0, 0.0); 1 << n_qubits];
+        state[0] = Complex64::new(1.0, 0.0);
+        StateVectorLayer { n_qubits, state, bits: MeasuredBits::new(), rng: Rng::new(seed) }
+    }
+
+    pub fn n_qubits(&self) -> u32 {
+        self.n_qubits
+    }
+
+    /// Gets the amplitudes. Qubit `q` corresponds to bit `q` of the index.
+    pub fn state(&self) -> &[Complex64] {
+        &self.state
+    }
+
+    fn initialize(&mut self) {
+        for a in self.state.iter_mut() {
+            *a = Complex64::new(0.0, 0.0);
+        }
+        self.state[0] = Complex64::new(1.0, 0.0);
+        self.bits.clear();
+    }
+
+    fn apply_single(&mut self, m: &Matrix2, q: u32) {
+        let mask = 1usize << q;
+        for i in 0..self.state.len() {
+            if i & mask == 0 {
+                let (a0, a1) = (self.state[i], self.state[i | mask]);
+                self.state[i] = m[0][0] * a0 + m[0][1] * a1;
+                self.state[i | mask] = m[1][0] * a0 + m[1][1] * a1;
+            }
+        }
+    }
+
+    fn apply_cx(&mut self, c: u32, t: u32) {
+        let (cmask, tmask) = (1usize << c, 1usize << t);
+        for i in 0..self.state.len() {
+            if i & cmask != 0 && i & tmask == 0 {
+                self.state.swap(i, i | tmask);
+            }
+        }
+    }
+
+    /// Projects qubit `q` to a random outcome and returns the outcome.
+    fn collapse(&mut self, q: u32) -> bool {
+        let mask = 1usize << q;
+        let p1: f64 = self.state.iter()
+                                .enumerate()
+                                .filter(|(i, _)| i & mask != 0)
+                                .map(|(_, a)| a.norm_sqr())
+                                .sum();
+        let result = self.rng.next_f64() < p1;
+        let norm = if result { p1 } else { 1.0 - p1 }.sqrt();
+        for (i, a) in self.state.iter_mut().enumerate() {
+            if (i & mask != 0) == result {
+                *a /= norm;
+            } else {
+                *a = Complex64::new(0.0, 0.0);
+            }
+        }
+        result
+    }
+
+    fn measure(&mut self, q: u32, s: u32) {
+        let result = self.collapse(q);
+        self.bits.set(s, result);
+    }
+
+    fn reset(&mut self, q: u32) {
+        if self.collapse(q) {
+            self.apply_single(&matrix::single(opid::X).unwrap(), q);
+        }
+    }
+
+    fn apply(&mut self, op: &OpArgs<Self>) {
+        match op {
+            OpArgs::Empty(opid::INIT) => self.initialize(),
+            OpArgs::QS(opid::MEAS, q, s) => self.measure(*q, *s),
+            OpArgs::Q(opid::RESET, q) => self.reset(*q),
+            OpArgs::If(_, slots, value, op) => if self.bits.holds(slots, *value) {
+                self.apply(op);
+            },
+            OpArgs::Q(_, q) | OpArgs::QD(_, q, _) | OpArgs::QDDD(_, q, _, _, _) => {
+                let m = matrix::gate(op).unwrap();
+                self.apply_single(&m, *q);
+            },
+            OpArgs::QQ(opid::CX, c, t) => self.apply_cx(*c, *t),
+            OpArgs::QQ(..) | OpArgs::QQQ(..) => {
+                for op in decompose(op) {
+                    self.apply(&op);
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Layer for StateVectorLayer {
+    type Operation = OpArgs<Self>;
+    type Qubit = u32;
+    type Slot = u32;
+    type Buffer = MeasuredBits;
+    type Requested = ();
+    type Response = ();
+
+    fn send(&mut self, ops: &[OpArgs<Self>]) {
+        self.try_send(ops).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn receive(&mut self, buf: &mut MeasuredBits) {
+        buf.clone_from(&self.bits);
+    }
+
+    fn make_buffer(&self) -> MeasuredBits {
+        MeasuredBits::new()
+    }
+}
+
+impl_try_layer!(StateVectorLayer, |op| matrix::gate(op).is_some() || is_multi(op));
+
+impl PauliGate for StateVectorLayer {}
+impl HGate for StateVectorLayer {}
+impl SGate for StateVectorLayer {}
+impl TGate for StateVectorLayer {}
+impl CXGate for StateVectorLayer {}
+impl RotationGate for StateVectorLayer {}
+impl U3Gate for StateVectorLayer {}
+impl CZGate for StateVectorLayer {}
+impl SwapGate for StateVectorLayer {}
+impl CCXGate for StateVectorLayer {}
+impl CYGate for StateVectorLayer {}
+impl ISwapGate for StateVectorLayer {}
+impl ConditionalGate for StateVectorLayer {}
+impl ResetGate for StateVectorLayer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, Measured, OpsVec};
+
+    #[test]
+    fn bell_pair() {
+        for seed in 0..20 {
+            let mut sim = StateVectorLayer::with_seed(2, seed);
+            let mut ops = sim.opsvec();
+            ops.initialize();
+            ops.h(0);
+            ops.cx(0, 1);
+            ops.measure(0, 0);
+            ops.measure(1, 1);
+            let mut buf = sim.make_buffer();
+            sim.send_receive(ops.as_ref(), &mut buf);
+            assert_eq!(buf.get(0), buf.get(1));
+        }
+    }
+
+    #[test]
+    fn phase_kickback() {
+        let mut sim = StateVectorLayer::new(1);
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.t(0);
+        ops.t(0);
+        ops.s(0);
+        ops.h(0);
+        ops.measure(0, 3);
+        let mut buf = sim.make_buffer();
+        sim.send_receive(ops.as_ref(), &mut buf);
+        assert!(buf.get(3));
+        assert!(!buf.get(0));
+    }
+
+    #[test]
+    fn shots() {
+        let mut sim = StateVectorLayer::with_seed(3, 7);
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.h(0);
+        ops.cx(0, 2);
+        ops.x(1);
+        for q in 0..3 {
+            ops.measure(q, q);
+        }
+        let counts = sim.run_shots(ops.as_ref(), 1000, 3);
+        assert_eq!(counts.shots(), 1000);
+        assert_eq!(counts.get(0b010) + counts.get(0b111), 1000);
+        assert!(counts.get(0b010) > 400 && counts.get(0b111) > 400);
+        assert_eq!(counts.bitstring(0b010), "010");
+    }
+
+    #[test]
+    fn rotations() {
+        use std::f64::consts::PI;
+        let mut sim = StateVectorLayer::new(2);
+        let mut ops = sim.opsvec();
+        ops.initialize();
+        ops.rx(0, PI);
+        ops.u3(1, PI / 2.0, 0.0, PI);
+        ops.rz(1, PI);
+        ops.ry(1, -PI / 2.0);
+        sim.send(ops.as_ref());
+        let expected = [0.0, 0.0, 0.0, 1.0];
+        for (a, e) in sim.state().iter().zip(expected.iter()) {
+            assert!((a.norm() - e).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn multi_qubit_gates() {
+        let mut sim = StateVectorLayer::new(3);
+        for input in 0..8 {
+            let mut ops = sim.opsvec();
+            ops.initialize();
+            for q in 0..3 {
+                if input & (1 << q) != 0 {
+                    ops.x(q);
+                }
+            }
+            ops.ccx(0, 1, 2);
+            sim.send(ops.as_ref());
+            let expected = if input & 0b011 == 0b011 { input ^ 0b100 } else { input };
+            assert!((sim.state()[expected] - Complex64::new(1.0, 0.0)).norm() < 1e-9);
+        }
+
+        type Build = fn(&mut OpsVec<StateVectorLayer>);
+        let i = Complex64::new(0.0, 1.0);
+        let cases: [(Build, usize, Complex64); 4] = [
+            (|ops| ops.swap(0, 1), 0b010, Complex64::new(1.0, 0.0)),
+            (|ops| { ops.h(1); ops.cz(0, 1); ops.h(1); }, 0b011, Complex64::new(1.0, 0.0)),
+            (|ops| ops.cy(0, 2), 0b101, i),
+            (|ops| ops.iswap(0, 1), 0b010, i),
+        ];
+        for (f, index, amp) in cases.iter() {
+            let mut ops = sim.opsvec();
+            ops.initialize();
+            ops.x(0);
+            f(&mut ops);
+            sim.send(ops.as_ref());
+            assert!((sim.state()[*index] - amp).norm() < 1e-9);
+        }
+
+        let ops = [OpArgs::QQ(opid::SWAP, 1, 1)];
+        assert!(matches!(sim.try_send(&ops), Err(Error::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn feed_forward() {
+        use std::f64::consts::PI;
+        for seed in 0..10 {
+            // Teleports RY(π/3)|0> from qubit 0 to qubit 2.
+            let mut sim = StateVectorLayer::with_seed(3, seed);
+            let mut ops = sim.opsvec();
+            ops.initialize();
+            ops.ry(0, PI / 3.0);
+            ops.h(1);
+            ops.cx(1, 2);
+            ops.cx(0, 1);
+            ops.h(0);
+            ops.measure(0, 0);
+            ops.measure(1, 1);
+            ops.if_slot(1, true, |ops| ops.x(2));
+            ops.if_slot(0, true, |ops| ops.z(2));
+            // Active reset of qubits 0 and 1.
+            ops.if_slots(vec![0, 1], 0b01, |ops| ops.x(0));
+            ops.if_slots(vec![0, 1], 0b10, |ops| ops.x(1));
+            ops.if_slots(vec![0, 1], 0b11, |ops| { ops.x(0); ops.x(1); });
+            sim.send(ops.as_ref());
+            assert!((sim.state()[0b000].norm() - (PI / 6.0).cos()).abs() < 1e-9);
+            assert!((sim.state()[0b100].norm() - (PI / 6.0).sin()).abs() < 1e-9);
+        }
+        let mut sim = StateVectorLayer::new(1);
+        let mut ops = sim.opsvec();
+        ops.if_slots(vec![0], 2, |ops| ops.x(0));
+        assert!(matches!(sim.try_send(ops.as_ref()), Err(Error::InvalidOperation(_))));
+        let mut ops = sim.opsvec();
+        ops.if_slot(0, true, |ops| { ops.measure(0, 0); ops.x(0); });
+        assert!(matches!(sim.try_send(ops.as_ref()), Err(Error::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn reset() {
+        for seed in 0..10 {
+            let mut sim = StateVectorLayer::with_seed(2, seed);
+            let mut ops = sim.opsvec();
+            ops.initialize();
+            ops.h(0);
+            ops.x(1);
+            ops.h(1);
+            ops.reset(0);
+            sim.send(ops.as_ref());
+            let h = std::f64::consts::FRAC_1_SQRT_2;
+            assert!((sim.state()[0b00] - Complex64::new(h, 0.0)).norm() < 1e-9);
+            assert!((sim.state()[0b10] - Complex64::new(-h, 0.0)).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut sim = StateVectorLayer::new(2);
+        let mut ops = sim.opsvec();
+        ops.x(0);
+        ops.x(2);
+        assert!(matches!(sim.try_send(ops.as_ref()), Err(Error::QubitOutOfRange(_))));
+        // Nothing is executed.
+        assert_eq!(sim.state()[0], Complex64::new(1.0, 0.0));
+        let ops = [OpArgs::Q(opid::USERDEF, 0)];
+        assert!(matches!(sim.try_send(&ops), Err(Error::UnsupportedOperation(opid::USERDEF))));
+        let mut ops = sim.opsvec();
+        ops.measure(0, u32::MAX);
+        assert!(matches!(sim.try_send(ops.as_ref()), Err(Error::SlotOutOfRange(_))));
+        let mut ops = sim.opsvec();
+        ops.if_slot(crate::sim::MAX_SLOTS, true, |ops| ops.x(0));
+        assert!(matches!(sim.try_send(ops.as_ref()), Err(Error::SlotOutOfRange(_))));
+        let mut ops = sim.opsvec();
+        ops.if_slot(0, false, |ops| {
+            ops.initialize();
+            ops.x(0);
+        });
+        assert!(matches!(sim.try_send(ops.as_ref()), Err(Error::InvalidOperation(_))));
+        let ops = [OpArgs::QD(opid::RX, 0, f64::NAN), OpArgs::QDDD(opid::U3, 0, 0.0, f64::INFINITY, 0.0)];
+        assert!(matches!(sim.try_send(&ops[..1]), Err(Error::InvalidOperation(_))));
+        assert!(matches!(sim.try_send(&ops[1..]), Err(Error::InvalidOperation(_))));
+    }
+}
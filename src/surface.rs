@@ -0,0 +1,293 @@
+//! Rotated surface code memory circuits on 2D coordinates.
+//!
+//! Qubits and slots are `(x, y)` coordinates, so the circuits can be sent to
+//! `SerializationLayer` with the width `SurfaceCode::width()` or larger.
+use crate::{Layer, Measured, OpsVec,
+            decoder::MatchingGraph,
+            gates::{HGate, CXGate, ResetGate},
+            operations::{Operation, HOperation, CXOperation, ResetOperation}};
+
+/// Basis of a stabilizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Basis {
+    X,
+    Z,
+}
+
+/// Stabilizer measured by the measure qubit at `pos`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stabilizer {
+    pub pos: (u32, u32),
+    pub basis: Basis,
+}
+
+/// Detector, which compares a stabilizer with its previous value.
+///
+/// For Z stabilizers, round 0 compares with the initial value and round `rounds` compares
+/// with the parity of the final data measurements. X stabilizers have rounds from 1 to `rounds - 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Detector {
+    pub stabilizer: Stabilizer,
+    pub round: u32,
+}
+
+/// Order of neighbors of CX gates. X and Z stabilizers use different orders so that they commute.
+const X_ORDER: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const Z_ORDER: [(i32, i32); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+
+/// Rotated surface code memory experiment in Z basis.
+///
+/// Data qubits are at `(2i + 1, 2j + 1)` and measure qubits are at `(2i, 2j)`.
+/// X stabilizers are on the top and bottom boundaries, and Z stabilizers are on the left
+/// and right boundaries. The logical Z is the row of data qubits at `y = 1`.
+/// Measurement of the measure qubit `(x, y)` in round `r` is written to slot `(x, y + r * width)`,
+/// and data qubits are measured into the slots of round `rounds`.
+#[derive(Debug, Clone)]
+pub struct SurfaceCode {
+    distance: u32,
+    rounds: u32,
+    data: Vec<(u32, u32)>,
+    stabilizers: Vec<Stabilizer>,
+}
+
+impl SurfaceCode {
+    /// Makes a code with given distance and number of syndrome extraction rounds.
+    pub fn new(distance: u32, rounds: u32) -> Self {
+        assert!(distance >= 2, "Distance should be 2 or more.");
+        assert!(rounds >= 1, "Rounds should be 1 or more.");
+        let d = distance;
+        let data = (0..d).flat_map(|j| (0..d).map(move |i| (2 * i + 1, 2 * j + 1))).collect();
+        let mut stabilizers = vec![];
+        for j in 0..=d {
+            for i in 0..=d {
+                let basis = if (i + j) % 2 == 0 { Basis::X } else { Basis::Z };
+                let on_x_boundary = (j == 0 || j == d) && 0 < i && i < d;
+                let on_z_boundary = (i == 0 || i == d) && 0 < j && j < d;
+                let inner = 0 < i && i < d && 0 < j && j < d;
+                if inner || (on_x_boundary && basis == Basis::X) || (on_z_boundary && basis == Basis::Z) {
+                    stabilizers.push(Stabilizer { pos: (2 * i, 2 * j), basis });
+                }
+            }
+        }
+        SurfaceCode { distance, rounds, data, stabilizers }
+    }
+
+    pub fn distance(&self) -> u32 {
+        self.distance
+    }
+
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    /// Gets the width of the coordinates.
+    pub fn width(&self) -> u32 {
+        2 * self.distance + 1
+    }
+
+    pub fn data_qubits(&self) -> &[(u32, u32)] {
+        &self.data
+    }
+
+    pub fn stabilizers(&self) -> &[Stabilizer] {
+        &self.stabilizers
+    }
+
+    /// Gets the data qubits of the logical Z operator.
+    pub fn logical_z(&self) -> Vec<(u32, u32)> {
+        self.data.iter().copied().filter(|&(_, y)| y == 1).collect()
+    }
+
+    fn neighbor(&self, pos: (u32, u32), (dx, dy): (i32, i32)) -> Option<(u32, u32)> {
+        let (x, y) = (pos.0 as i32 + dx, pos.1 as i32 + dy);
+        let max = 2 * self.distance as i32;
+        if 0 < x && x < max && 0 < y && y < max {
+            Some((x as u32, y as u32))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the data qubits checked by the stabilizer.
+    pub fn support(&self, s: &Stabilizer) -> Vec<(u32, u32)> {
+        X_ORDER.iter().filter_map(|&d| self.neighbor(s.pos, d)).collect()
+    }
+
+    /// Gets the slot for the measurement of qubit `q` in round `round`.
+    pub fn slot(&self, q: (u32, u32), round: u32) -> (u32, u32) {
+        (q.0, q.1 + round * self.width())
+    }
+
+    /// Gets all detectors.
+    pub fn detectors(&self) -> Vec<Detector> {
+        let mut detectors = vec![];
+        for round in 0..=self.rounds {
+            for &stabilizer in &self.stabilizers {
+                if stabilizer.basis == Basis::Z || (0 < round && round < self.rounds) {
+                    detectors.push(Detector { stabilizer, round });
+                }
+            }
+        }
+        detectors
+    }
+
+    /// Gets the detectors which are fired by measured results.
+    pub fn detection_events<M: Measured<Slot=(u32, u32)>>(&self, m: &M) -> Vec<Detector> {
+        self.detectors().into_iter().filter(|d| self.detector_value(d, m)).collect()
+    }
+
+    fn detector_value<M: Measured<Slot=(u32, u32)>>(&self, d: &Detector, m: &M) -> bool {
+        let pos = d.stabilizer.pos;
+        if d.round == 0 {
+            m.get(self.slot(pos, 0))
+        } else if d.round == self.rounds {
+            let parity = self.support(&d.stabilizer).into_iter().fold(false, |p, q| p ^ m.get(self.slot(q, d.round)));
+            parity ^ m.get(self.slot(pos, d.round - 1))
+        } else {
+            m.get(self.slot(pos, d.round)) ^ m.get(self.slot(pos, d.round - 1))
+        }
+    }
+
+    fn z_stabilizers(&self) -> impl Iterator<Item=&Stabilizer> {
+        self.stabilizers.iter().filter(|s| s.basis == Basis::Z)
+    }
+
+    /// Gets the node of the detector in `matching_graph`, or `None` for X stabilizers.
+    pub fn node(&self, d: &Detector) -> Option<usize> {
+        let n_z = self.z_stabilizers().count();
+        let index = self.z_stabilizers().position(|s| *s == d.stabilizer)?;
+        Some(d.round as usize * n_z + index)
+    }
+
+    /// Makes the matching graph of Z detectors with unit weights.
+    ///
+    /// Edges are X errors on data qubits before each round or the data measurement,
+    /// and measurement errors of Z stabilizers.
+    pub fn matching_graph(&self) -> MatchingGraph {
+        let n_z = self.z_stabilizers().count();
+        let logical = self.logical_z();
+        let mut graph = MatchingGraph::new(n_z * (self.rounds as usize + 1));
+        for round in 0..=self.rounds as usize {
+            let offset = round * n_z;
+            for q in &self.data {
+                let nodes: Vec<_> = self.z_stabilizers().enumerate()
+                                        .filter(|(_, s)| self.support(s).contains(q))
+                                        .map(|(i, _)| offset + i).collect();
+                match nodes[..] {
+                    [a] => graph.add_boundary_edge(a, 1.0, logical.contains(q)),
+                    [a, b] => graph.add_edge(a, b, 1.0, logical.contains(q)),
+                    _ => {},
+                }
+            }
+            if round < self.rounds as usize {
+                for i in 0..n_z {
+                    graph.add_edge(offset + i, offset + n_z + i, 1.0, false);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Gets the measured value of the logical Z operator.
+    pub fn logical_observable<M: Measured<Slot=(u32, u32)>>(&self, m: &M) -> bool {
+        self.logical_z().into_iter().fold(false, |p, q| p ^ m.get(self.slot(q, self.rounds)))
+    }
+}
+
+impl SurfaceCode {
+    /// Makes the whole memory circuit: initialize, syndrome extraction rounds and data measurement.
+    pub fn circuit<L>(&self) -> OpsVec<L>
+        where L: Layer<Qubit=(u32, u32), Slot=(u32, u32)> + HGate + CXGate + ResetGate,
+              L::Operation: Operation<L> + HOperation<L> + CXOperation<L> + ResetOperation<L>,
+    {
+        let mut ops = OpsVec::new();
+        ops.initialize();
+        for round in 0..self.rounds {
+            self.syndrome_round(&mut ops, round);
+        }
+        self.measure_data(&mut ops);
+        ops
+    }
+
+    /// Adds a syndrome extraction round, which measures all stabilizers.
+    pub fn syndrome_round<L>(&self, ops: &mut OpsVec<L>, round: u32)
+        where L: Layer<Qubit=(u32, u32), Slot=(u32, u32)> + HGate + CXGate + ResetGate,
+              L::Operation: Operation<L> + HOperation<L> + CXOperation<L> + ResetOperation<L>,
+    {
+        let x_stabilizers = || self.stabilizers.iter().filter(|s| s.basis == Basis::X);
+        for s in &self.stabilizers {
+            ops.reset(s.pos);
+        }
+        for s in x_stabilizers() {
+            ops.h(s.pos);
+        }
+        for i in 0..4 {
+            for s in &self.stabilizers {
+                match s.basis {
+                    Basis::X => if let Some(q) = self.neighbor(s.pos, X_ORDER[i]) {
+                        ops.cx(s.pos, q);
+                    },
+                    Basis::Z => if let Some(q) = self.neighbor(s.pos, Z_ORDER[i]) {
+                        ops.cx(q, s.pos);
+                    },
+                }
+            }
+        }
+        for s in x_stabilizers() {
+            ops.h(s.pos);
+        }
+        for s in &self.stabilizers {
+            ops.measure(s.pos, self.slot(s.pos, round));
+        }
+    }
+
+    /// Adds measurements of all data qubits into the slots of round `rounds`.
+    pub fn measure_data<L>(&self, ops: &mut OpsVec<L>)
+        where L: Layer<Qubit=(u32, u32), Slot=(u32, u32)>,
+              L::Operation: Operation<L>,
+    {
+        for &q in &self.data {
+            ops.measure(q, self.slot(q, self.rounds));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert::SerializationLayer, sim::StabilizerLayer};
+
+    type Surface = SerializationLayer<StabilizerLayer, 7>;
+
+    #[test]
+    fn memory() {
+        let code = SurfaceCode::new(3, 3);
+        assert_eq!(code.stabilizers().len(), 8);
+        assert_eq!(code.detectors().len(), 4 * 4 + 4 * 2);
+        assert_eq!(code.width(), 7);
+
+        for seed in 0..5 {
+            let mut layer = Surface::new(StabilizerLayer::with_seed(49, seed));
+            let ops: OpsVec<Surface> = code.circuit();
+            let mut buf = layer.make_buffer();
+            layer.send_receive(ops.as_ref(), &mut buf);
+            assert!(code.detection_events(&buf).is_empty());
+            assert!(!code.logical_observable(&buf));
+
+            // X error on the data qubit at the center after the first round.
+            let mut ops = layer.opsvec();
+            ops.initialize();
+            code.syndrome_round(&mut ops, 0);
+            ops.x((3, 3));
+            code.syndrome_round(&mut ops, 1);
+            code.syndrome_round(&mut ops, 2);
+            code.measure_data(&mut ops);
+            layer.send_receive(ops.as_ref(), &mut buf);
+            let mut events: Vec<_> = code.detection_events(&buf).into_iter()
+                                          .map(|d| (d.stabilizer.pos, d.round)).collect();
+            events.sort_unstable();
+            assert_eq!(events, vec![((2, 4), 1), ((4, 2), 1)]);
+            assert!(!code.logical_observable(&buf));
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Operation types for wrapper layers.
+//!
+//! A wrapper layer, which forwards operations to the inner layer, needs its own
+//! `Operation` type to implement operation traits for itself. Such operation types are
+//! `#[repr(transparent)]` newtypes of inner operations, and slices of them are converted
+//! into slices of inner operations without copying via `TransparentWrapper`.
+//!
+//! Define such types by `transparent_wrapper!`, which also implements `TransparentWrapper`.
+
+/// Zero-cost conversion from slices of wrapped operations to slices of inner operations.
+///
+/// # Safety
+/// `Self` must be a struct with `#[repr(transparent)]` whose only non-zero-sized field is
+/// of type `Inner`, and the other fields must be `PhantomData`.
+/// Checking size and alignment is not enough, because the layout of a struct without
+/// `#[repr(transparent)]` is unspecified.
+/// Prefer `transparent_wrapper!`, which defines the struct and implements this trait together.
+pub unsafe trait TransparentWrapper : Sized {
+    type Inner;
+
+    fn peel_slice(s: &[Self]) -> &[Self::Inner] {
+        // SAFETY: `Self` is `#[repr(transparent)]` over `Inner` as the trait requires.
+        unsafe { std::slice::from_raw_parts(s.as_ptr() as *const Self::Inner, s.len()) }
+    }
+}
+
+/// Defines a `#[repr(transparent)]` newtype `Name<L, ..>(L::Operation, PhantomData<..>)`
+/// over the operation of a layer `L`, and implements `TransparentWrapper`, `Debug`, `Clone`,
+/// `PartialEq` and `Eq` for it.
+///
+/// The field is private to the module where the macro is called, so define constructors there.
+#[macro_export]
+macro_rules! transparent_wrapper {
+    ($(#[$attr:meta])* $vis:vis struct $name:ident<L $(, $param:ident)*>;) => {
+        $(#[$attr])*
+        #[repr(transparent)]
+        #[allow(clippy::type_complexity)]
+        $vis struct $name<L: $crate::Layer $(, $param)*>(
+            L::Operation, std::marker::PhantomData<fn() -> (L, $($param,)*)>);
+
+        // SAFETY: The struct is `#[repr(transparent)]` and the other field is `PhantomData`.
+        unsafe impl<L: $crate::Layer $(, $param)*> $crate::wrapped::TransparentWrapper for $name<L $(, $param)*> {
+            type Inner = L::Operation;
+        }
+
+        impl<L: $crate::Layer $(, $param)*> std::fmt::Debug for $name<L $(, $param)*>
+            where L::Operation: std::fmt::Debug
+        {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.0).finish()
+            }
+        }
+
+        impl<L: $crate::Layer $(, $param)*> Clone for $name<L $(, $param)*>
+            where L::Operation: Clone
+        {
+            fn clone(&self) -> Self {
+                $name(self.0.clone(), std::marker::PhantomData)
+            }
+        }
+
+        impl<L: $crate::Layer $(, $param)*> PartialEq for $name<L $(, $param)*>
+            where L::Operation: PartialEq
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl<L: $crate::Layer $(, $param)*> Eq for $name<L $(, $param)*> where L::Operation: Eq {}
+    }
+}
+
+pub use transparent_wrapper;